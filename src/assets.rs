@@ -0,0 +1,183 @@
+// ASSET MANIFEST
+//
+// Every audio and sprite id the code asks Turbo for, so the files in audio/
+// and sprites/ can be checked against it (by `cargo test`, or src/bin/assets.rs
+// for a readable report). Turbo finds
+// assets by file name without the extension, which is what the ids match.
+
+use crate::sound::{Music, Sfx};
+
+/// Sprite ids passed to `sprite!` (everything is drawn with shapes for now)
+pub const SPRITES: &[&str] = &[];
+
+/// Documentation kept next to the assets
+const IGNORED_FILES: &[&str] = &["README.md"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Audio,
+    Sprite,
+}
+
+impl AssetKind {
+    pub fn dir(self) -> &'static str {
+        match self {
+            Self::Audio => "audio",
+            Self::Sprite => "sprites",
+        }
+    }
+}
+
+/// One id the code references, and what for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AssetRef {
+    pub kind: AssetKind,
+    pub id: &'static str,
+    pub used_for: String,
+}
+
+/// Every asset reference in the game
+pub fn manifest() -> Vec<AssetRef> {
+    let music = Music::ALL.iter().map(|m| AssetRef {
+        kind: AssetKind::Audio,
+        id: m.asset(),
+        used_for: format!("music {m:?}"),
+    });
+    let sfx = Sfx::ALL.iter().map(|s| AssetRef {
+        kind: AssetKind::Audio,
+        id: s.asset(),
+        used_for: format!("sfx {s:?}"),
+    });
+    let sprites = SPRITES.iter().map(|&id| AssetRef {
+        kind: AssetKind::Sprite,
+        id,
+        used_for: "sprite".to_string(),
+    });
+    music.chain(sfx).chain(sprites).collect()
+}
+
+/// Result of comparing the manifest with the files on disk
+#[derive(Debug, Default)]
+pub struct AssetReport {
+    pub missing: Vec<String>,    // Referenced but no file
+    pub unused: Vec<String>,     // File that nothing references
+    pub ambiguous: Vec<String>,  // Names that could resolve to the wrong thing
+}
+
+impl AssetReport {
+    /// Nothing missing and every id resolves to exactly one file
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.ambiguous.is_empty()
+    }
+}
+
+/// Names of the files in one asset folder under `root`, sorted
+pub fn list(root: &std::path::Path, kind: AssetKind) -> std::io::Result<Vec<String>> {
+    let mut files: Vec<String> = std::fs::read_dir(root.join(kind.dir()))?
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+fn stem(file: &str) -> &str {
+    file.rsplit_once('.').map_or(file, |(stem, _)| stem)
+}
+
+/// Names that read the same once case and `-`/`_` are ignored
+fn loose(name: &str) -> String {
+    name.to_ascii_lowercase().replace('-', "_")
+}
+
+/// Compare the manifest against the file names found in one asset folder
+pub fn check(kind: AssetKind, files: &[String], refs: &[AssetRef]) -> AssetReport {
+    let mut report = AssetReport::default();
+    let dir = kind.dir();
+    let files: Vec<&str> = files
+        .iter()
+        .map(String::as_str)
+        .filter(|f| !IGNORED_FILES.contains(f) && !f.starts_with('.'))
+        .collect();
+    let refs: Vec<&AssetRef> = refs.iter().filter(|r| r.kind == kind).collect();
+
+    for r in &refs {
+        if !files.iter().any(|f| stem(f) == r.id) {
+            report.missing.push(format!("{dir}/{}.* ({})", r.id, r.used_for));
+        }
+    }
+
+    for file in &files {
+        if !refs.iter().any(|r| r.id == stem(file)) {
+            report.unused.push(format!("{dir}/{file}"));
+        }
+    }
+
+    // Same id in several formats: which one loads is up to the runtime
+    for (i, a) in files.iter().enumerate() {
+        for b in &files[i + 1..] {
+            if stem(a) == stem(b) {
+                report.ambiguous.push(format!("{dir}/{a} and {dir}/{b} share the id \"{}\"", stem(a)));
+            } else if loose(stem(a)) == loose(stem(b)) {
+                report.ambiguous.push(format!("{dir}/{a} and {dir}/{b} differ only by case or -/_"));
+            }
+        }
+    }
+
+    // One file doing two jobs, e.g. a looping track also fired as a one-shot
+    for (i, a) in refs.iter().enumerate() {
+        for b in &refs[i + 1..] {
+            if a.id == b.id {
+                report.ambiguous.push(format!("\"{}\" is used as both {} and {}", a.id, a.used_for, b.used_for));
+            }
+        }
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_referenced_asset_is_on_disk() {
+        let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+        let refs = manifest();
+        for kind in [AssetKind::Audio, AssetKind::Sprite] {
+            let files = list(root, kind).unwrap_or_else(|err| panic!("cannot read {}/: {err}", kind.dir()));
+            let report = check(kind, &files, &refs);
+            assert!(report.missing.is_empty(), "missing from {}/: {:?}", kind.dir(), report.missing);
+            assert!(report.ambiguous.is_empty(), "ambiguous in {}/: {:?}", kind.dir(), report.ambiguous);
+            assert!(report.unused.is_empty(), "unused in {}/: {:?}", kind.dir(), report.unused);
+        }
+    }
+
+    #[test]
+    fn reports_missing_unused_and_ambiguous_files() {
+        let refs = vec![
+            AssetRef { kind: AssetKind::Audio, id: "jingle", used_for: "sfx Jingle".to_string() },
+            AssetRef { kind: AssetKind::Audio, id: "bells", used_for: "music Bells".to_string() },
+        ];
+        let files: Vec<String> = ["jingle.wav", "jingle.mp3", "old_tune.ogg", "Old-Tune.mp3", "README.md"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let report = check(AssetKind::Audio, &files, &refs);
+        assert!(!report.is_ok());
+        assert_eq!(report.missing, vec!["audio/bells.* (music Bells)"]);
+        assert_eq!(report.unused, vec!["audio/old_tune.ogg", "audio/Old-Tune.mp3"]);
+        assert_eq!(report.ambiguous.len(), 2);
+
+        // One file per id, but a music track also fired as an effect
+        let refs = vec![
+            AssetRef { kind: AssetKind::Audio, id: "bells", used_for: "music Bells".to_string() },
+            AssetRef { kind: AssetKind::Audio, id: "bells", used_for: "sfx Bells".to_string() },
+        ];
+        let report = check(AssetKind::Audio, &["bells.ogg".to_string()], &refs);
+        assert!(report.missing.is_empty());
+        assert_eq!(report.ambiguous.len(), 1);
+        assert!(!report.is_ok());
+    }
+}
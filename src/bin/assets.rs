@@ -0,0 +1,46 @@
+// ASSET CHECKER
+//
+// Compares the sound and sprite ids the game references with the files in
+// audio/ and sprites/:
+//
+//     cargo run --bin assets
+//
+// `cargo test` runs the same check and also fails on unused files.
+//
+// Missing and ambiguous assets fail the check; unused ones are reported as warnings.
+
+use game::assets::{check, list, manifest, AssetKind};
+use std::path::Path;
+use std::process::ExitCode;
+
+fn files(kind: AssetKind) -> Vec<String> {
+    list(Path::new(env!("CARGO_MANIFEST_DIR")), kind).unwrap_or_else(|err| {
+        eprintln!("warning: cannot read {}/: {err}", kind.dir());
+        vec![]
+    })
+}
+
+fn main() -> ExitCode {
+    let refs = manifest();
+    let mut ok = true;
+
+    for kind in [AssetKind::Audio, AssetKind::Sprite] {
+        let report = check(kind, &files(kind), &refs);
+        println!("{}/", kind.dir());
+        for line in &report.missing {
+            println!("  MISSING    {line}");
+        }
+        for line in &report.ambiguous {
+            println!("  AMBIGUOUS  {line}");
+        }
+        for line in &report.unused {
+            println!("  UNUSED     {line}");
+        }
+        if report.missing.is_empty() && report.ambiguous.is_empty() && report.unused.is_empty() {
+            println!("  ok");
+        }
+        ok &= report.is_ok();
+    }
+
+    if ok { ExitCode::SUCCESS } else { ExitCode::FAILURE }
+}
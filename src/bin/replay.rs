@@ -0,0 +1,39 @@
+// REPLAY VERIFIER
+//
+// Plays recorded runs headlessly and checks they reproduce their final stats:
+//
+//     cargo run --bin replay -- run1.sdr run2.txt
+//
+// Accepts raw replay files or the hex text the game exports on game over.
+
+use game::replay::Replay;
+use std::process::ExitCode;
+
+fn load(path: &str) -> Result<Replay, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    Replay::from_file(&bytes).map_err(|e| e.to_string())
+}
+
+fn main() -> ExitCode {
+    let paths: Vec<String> = std::env::args().skip(1).collect();
+    if paths.is_empty() {
+        eprintln!("usage: replay <file>...");
+        return ExitCode::FAILURE;
+    }
+
+    let mut failed = false;
+    for path in &paths {
+        match load(path).and_then(|replay| replay.verify().map_err(|e| e.to_string())) {
+            Ok(summary) => println!(
+                "{path}: OK (score {}, deliveries {}, max combo {})",
+                summary.score, summary.deliveries, summary.max_combo
+            ),
+            Err(err) => {
+                println!("{path}: FAILED ({err})");
+                failed = true;
+            }
+        }
+    }
+
+    if failed { ExitCode::FAILURE } else { ExitCode::SUCCESS }
+}
@@ -0,0 +1,222 @@
+// CAMPAIGN NIGHTS
+//
+// The campaign is a run of authored nights, December 1st to 24th, read from
+// campaign.toml (embedded at build time). Like levels.toml, missing fields
+// inherit from the night before and out-of-range values fall back with a
+// warning.
+
+use crate::tuning::pick;
+use turbo::serde::Deserialize;
+
+pub const CAMPAIGN_TOML: &str = include_str!("../campaign.toml");
+
+/// One night per day of advent
+pub const MAX_NIGHTS: usize = 24;
+
+/// One night of the campaign
+#[turbo::serialize]
+pub struct Night {
+    pub name: String,
+    pub level: u32,          // levels.toml level it plays at
+    pub target: u32,         // Deliveries needed to clear it
+    pub route: f32,          // Pixels to fly, not counting Krampus fights
+    pub krampus: Vec<f32>,   // Fractions of the route where Krampus attacks, in order
+    pub stars: (u32, u32),   // Scores for two and three stars
+}
+
+impl Default for Night {
+    fn default() -> Self {
+        Self {
+            name: "Night".to_string(),
+            level: 1,
+            target: 8,
+            route: 5000.0,
+            krampus: vec![],
+            stars: (3200, 5600),
+        }
+    }
+}
+
+impl Night {
+    /// Stars for finishing with these stats: none unless the night was cleared
+    pub fn rating(&self, cleared: bool, score: u32) -> u8 {
+        match cleared {
+            false => 0,
+            true if score >= self.stars.1 => 3,
+            true if score >= self.stars.0 => 2,
+            true => 1,
+        }
+    }
+}
+
+/// Every night of the campaign, in order
+#[turbo::serialize]
+pub struct Campaign {
+    pub nights: Vec<Night>,
+}
+
+// Raw file shape: every field optional so nights can inherit
+#[derive(Deserialize, Default)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawCampaign {
+    night: Vec<RawNight>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawNight {
+    name: Option<String>,
+    level: Option<u32>,
+    target: Option<u32>,
+    route: Option<f32>,
+    krampus: Option<Vec<f32>>,
+    stars: Option<(u32, u32)>,
+}
+
+impl Campaign {
+    /// The campaign built into the game, or a generated one if it fails to parse
+    pub fn embedded() -> Self {
+        Self::parse(CAMPAIGN_TOML).0
+    }
+
+    /// Twenty-four plain nights getting steadily longer and harder
+    pub fn fallback() -> Self {
+        let nights = (0..MAX_NIGHTS as u32)
+            .map(|i| {
+                let target = 8 + i;
+                Night {
+                    name: format!("Night {}", i + 1),
+                    level: 1 + i * 10 / 23,
+                    target,
+                    route: 5000.0 + i as f32 * 400.0,
+                    krampus: if i >= 2 { vec![0.5] } else { vec![] },
+                    stars: (target * 400, target * 700),
+                }
+            })
+            .collect();
+        Self { nights }
+    }
+
+    /// Parse and validate the campaign, returning any problems found. Never fails.
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut warnings = vec![];
+        let raw: RawCampaign = match toml::from_str(text) {
+            Ok(raw) => raw,
+            Err(err) => {
+                warnings.push(format!("campaign.toml: {err}; using plain nights"));
+                return (Self::fallback(), warnings);
+            }
+        };
+
+        if raw.night.len() > MAX_NIGHTS {
+            warnings.push(format!("campaign.toml: {} nights listed, only the first {MAX_NIGHTS} are used", raw.night.len()));
+        }
+        let mut nights: Vec<Night> = vec![];
+        let mut prev = Night::default();
+        for (i, raw) in raw.night.into_iter().take(MAX_NIGHTS).enumerate() {
+            let n = i + 1;
+            let place = format!("night {n}");
+            let w = &mut warnings;
+            let mut krampus = raw.krampus.unwrap_or_else(|| prev.krampus.clone());
+            if krampus.iter().any(|&at| !(0.0..1.0).contains(&at)) {
+                w.push(format!("campaign.toml: night {n}: krampus points must be from 0 up to 1, skipping the others"));
+                krampus.retain(|&at| (0.0..1.0).contains(&at));
+            }
+            krampus.sort_by(f32::total_cmp);
+            let night = Night {
+                name: raw.name.unwrap_or_else(|| format!("Night {n}")),
+                level: pick(w, "campaign.toml", &place, "level", raw.level, prev.level, |v| (1..=99).contains(&v)),
+                target: pick(w, "campaign.toml", &place, "target", raw.target, prev.target, |v| (1..=500).contains(&v)),
+                route: pick(w, "campaign.toml", &place, "route", raw.route, prev.route, |v| (1000.0..=200000.0).contains(&v)),
+                krampus,
+                stars: pick(w, "campaign.toml", &place, "stars", raw.stars, prev.stars, |(two, three)| two > 0 && three >= two),
+            };
+            prev = night.clone();
+            nights.push(night);
+        }
+
+        if nights.is_empty() {
+            warnings.push("campaign.toml: no [[night]] entries; using plain nights".to_string());
+            return (Self::fallback(), warnings);
+        }
+        (Self { nights }, warnings)
+    }
+
+    /// A 1-based night, if the campaign has it
+    pub fn get(&self, night: u8) -> Option<&Night> {
+        self.nights.get((night as usize).checked_sub(1)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_embedded_campaign_has_every_night_of_advent() {
+        let (campaign, warnings) = Campaign::parse(CAMPAIGN_TOML);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(campaign.nights.len(), MAX_NIGHTS);
+        assert!(campaign.get(0).is_none());
+        assert!(campaign.get(24).is_some());
+        assert!(campaign.get(25).is_none());
+    }
+
+    #[test]
+    fn nights_past_christmas_eve_are_dropped() {
+        let text = "[[night]]\ntarget = 10\n".repeat(MAX_NIGHTS + 3);
+        let (campaign, warnings) = Campaign::parse(&text);
+        assert_eq!(campaign.nights.len(), MAX_NIGHTS);
+        assert_eq!(warnings, ["campaign.toml: 27 nights listed, only the first 24 are used"]);
+    }
+
+    #[test]
+    fn krampus_points_outside_the_route_are_dropped() {
+        let (campaign, warnings) = Campaign::parse(r#"
+            [[night]]
+            krampus = [0.8, 1.0, -0.2, 0.3, 1.5]
+
+            [[night]]
+            name = "Quiet"
+        "#);
+        assert_eq!(campaign.nights[0].krampus, vec![0.3, 0.8]);
+        assert_eq!(campaign.nights[1].krampus, vec![0.3, 0.8], "left out, they carry on");
+        assert_eq!(campaign.nights[1].name, "Quiet");
+        assert_eq!(warnings, ["campaign.toml: night 1: krampus points must be from 0 up to 1, skipping the others"]);
+    }
+
+    #[test]
+    fn out_of_range_values_keep_the_night_before() {
+        let (campaign, warnings) = Campaign::parse(r#"
+            [[night]]
+            target = 12
+
+            [[night]]
+            target = 0
+            stars = [500, 100]
+        "#);
+        assert_eq!(campaign.nights[1].target, 12);
+        assert_eq!(campaign.nights[1].stars, Night::default().stars);
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+    }
+
+    #[test]
+    fn an_unusable_file_falls_back_to_plain_nights() {
+        for text in ["[[night]", "[[night]]\nsnow = true", ""] {
+            let (campaign, warnings) = Campaign::parse(text);
+            assert_eq!(warnings.len(), 1, "{text:?}: {warnings:?}");
+            assert_eq!(campaign.nights.len(), MAX_NIGHTS);
+        }
+    }
+
+    #[test]
+    fn stars_are_earned_at_the_thresholds() {
+        let night = Night { stars: (1000, 2000), ..Night::default() };
+        assert_eq!(night.rating(false, 5000), 0);
+        assert_eq!(night.rating(true, 0), 1);
+        assert_eq!(night.rating(true, 999), 1);
+        assert_eq!(night.rating(true, 1000), 2);
+        assert_eq!(night.rating(true, 1999), 2);
+        assert_eq!(night.rating(true, 2000), 3);
+    }
+}
@@ -0,0 +1,109 @@
+// DAILY CHALLENGE
+//
+// Everyone playing on the same calendar day (UTC) gets the same seed, so the
+// same town, enemies and power-ups. Days are counted from the Unix epoch; the
+// date itself only matters for display and the share text.
+
+const MS_PER_DAY: u64 = 86_400_000;
+
+/// Clocks reading earlier than this (2024-01-01) are treated as missing
+const EARLIEST_DAY: u32 = 19_723;
+
+/// Used when neither the clock nor the save has a usable day (2024-12-01)
+pub const FALLBACK_DAY: u32 = 20_058;
+
+/// Today's day number from a wall clock in ms since the epoch, if it looks set
+pub fn day_from_millis(ms: u64) -> Option<u32> {
+    let day = (ms / MS_PER_DAY) as u32;
+    (day >= EARLIEST_DAY).then_some(day)
+}
+
+/// The day to play: the clock's, else the last one saved, else `FALLBACK_DAY`
+pub fn today(now_ms: u64, saved_day: u32) -> u32 {
+    day_from_millis(now_ms)
+        .or((saved_day != 0).then_some(saved_day))
+        .unwrap_or(FALLBACK_DAY)
+}
+
+/// The run seed for a day
+pub fn seed(day: u32) -> u32 {
+    // Mix the bits so neighbouring days don't get neighbouring seeds
+    let mut x = day.wrapping_mul(0x9E37_79B9) ^ 0x5A17_A000;
+    x ^= x >> 16;
+    x = x.wrapping_mul(0x85EB_CA6B);
+    x ^= x >> 13;
+    x
+}
+
+/// Year, month and day of a day number (proleptic Gregorian calendar)
+pub fn civil_date(day: u32) -> (i32, u32, u32) {
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = (yoe + era * 400 + if m <= 2 { 1 } else { 0 }) as i32;
+    (y, m, d)
+}
+
+/// A day as YYYY-MM-DD
+pub fn date_string(day: u32) -> String {
+    let (y, m, d) = civil_date(day);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+/// One line to paste anywhere to compare results
+pub fn share_text(day: u32, score: u32, accuracy: u32) -> String {
+    format!("Santa Delivery Daily {}: {score} points, {accuracy}% accuracy", date_string(day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_day_always_gets_the_same_seed() {
+        assert_eq!(seed(FALLBACK_DAY), seed(FALLBACK_DAY));
+        // Pinned so a change to the mixing can't quietly reshuffle past days
+        assert_eq!(seed(FALLBACK_DAY), 2_765_455_915);
+        let seeds: Vec<u32> = (FALLBACK_DAY..FALLBACK_DAY + 30).map(seed).collect();
+        for pair in seeds.windows(2) {
+            assert_ne!(pair[0], pair[1]);
+        }
+    }
+
+    #[test]
+    fn an_unset_clock_has_no_day() {
+        assert_eq!(day_from_millis(0), None);
+        assert_eq!(day_from_millis(EARLIEST_DAY as u64 * MS_PER_DAY - 1), None);
+        assert_eq!(day_from_millis(EARLIEST_DAY as u64 * MS_PER_DAY), Some(EARLIEST_DAY));
+        // Any time during the day is the same day
+        let noon = FALLBACK_DAY as u64 * MS_PER_DAY + MS_PER_DAY / 2;
+        assert_eq!(day_from_millis(noon), Some(FALLBACK_DAY));
+    }
+
+    #[test]
+    fn without_a_clock_the_saved_day_or_the_fallback_is_played() {
+        let noon = FALLBACK_DAY as u64 * MS_PER_DAY + MS_PER_DAY / 2;
+        assert_eq!(today(noon, 20_000), FALLBACK_DAY);
+        assert_eq!(today(0, 20_000), 20_000);
+        assert_eq!(today(0, 0), FALLBACK_DAY);
+        // A fallback day still plays as that day, with its own seed
+        assert_eq!(seed(today(0, 0)), seed(FALLBACK_DAY));
+    }
+
+    #[test]
+    fn days_are_shown_as_utc_dates() {
+        assert_eq!(date_string(0), "1970-01-01");
+        assert_eq!(date_string(EARLIEST_DAY), "2024-01-01");
+        assert_eq!(date_string(FALLBACK_DAY), "2024-12-01");
+        assert_eq!(date_string(19_782), "2024-02-29");
+        assert_eq!(
+            share_text(FALLBACK_DAY, 1234, 87),
+            "Santa Delivery Daily 2024-12-01: 1234 points, 87% accuracy"
+        );
+    }
+}
@@ -0,0 +1,179 @@
+// GHOST RACING
+//
+// A ghost is a replay played back alongside a live run on the same seed. The
+// game is deterministic, so the recorded input alone rebuilds every move the
+// ghost made. It runs its own `Simulation`, stepped once for every tick of
+// the live one.
+//
+// Ghosts come from two places: the personal bests kept in the save (one per
+// run mode and player count), and someone else's run imported on the title
+// screen as the hex text the game exports. The hex is read from the keyboard,
+// since that is the only way the runtime passes text to a running game.
+
+use crate::replay::Replay;
+use crate::sim::Simulation;
+use crate::types::GameMode;
+
+/// Which ghost to race on the next run
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum GhostSource {
+    #[default]
+    Off,
+    Best,      // Personal best from the save
+    Imported,  // Typed or pasted in on the title screen
+}
+
+impl GhostSource {
+    pub const ALL: [GhostSource; 3] = [Self::Off, Self::Best, Self::Imported];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "Off",
+            Self::Best => "Personal best",
+            Self::Imported => "Imported",
+        }
+    }
+
+    /// The next choice along, wrapping around
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&s| s == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// Longest hex text the import screen takes (a 256 KB replay)
+pub const MAX_IMPORT_LEN: usize = 512 * 1024;
+
+/// Hex text for a ghost being typed or pasted in, a key at a time
+#[turbo::serialize]
+#[derive(Default)]
+pub struct GhostImport {
+    pub hex: String,
+    pub error: Option<String>,  // Why the last attempt didn't load
+}
+
+impl GhostImport {
+    /// Take the characters typed this frame; anything but hex digits is ignored
+    pub fn type_chars(&mut self, chars: &[char]) {
+        for c in chars.iter().filter(|c| c.is_ascii_hexdigit()) {
+            if self.hex.len() >= MAX_IMPORT_LEN { break; }
+            self.hex.push(c.to_ascii_lowercase());
+            self.error = None;
+        }
+    }
+
+    pub fn backspace(&mut self) {
+        self.hex.pop();
+        self.error = None;
+    }
+
+    /// Read the text as a replay, keeping the reason if it isn't one
+    pub fn finish(&mut self) -> Option<Replay> {
+        match Replay::from_hex(&self.hex) {
+            Ok(replay) => Some(replay),
+            Err(err) => {
+                self.error = Some(err.to_string());
+                None
+            }
+        }
+    }
+}
+
+/// A replay being raced
+#[turbo::serialize]
+pub struct Ghost {
+    pub replay: Replay,
+    pub sim: Simulation,
+    pub tick: u32,
+}
+
+impl Ghost {
+    /// Line the ghost up at the start of its run
+    pub fn new(replay: Replay) -> Self {
+        let sim = replay.start();
+        Self { replay, sim, tick: 0 }
+    }
+
+    /// Advance one tick of play with the next recorded input; does nothing once
+    /// the run is over. Wherever the recorded run was paused the ghost skips
+    /// ahead, so it keeps pace with the live run's playing time.
+    pub fn step(&mut self) {
+        self.step_frame();
+        while self.sim.mode == GameMode::Paused && (self.tick as usize) < self.replay.frames.len() {
+            self.step_frame();
+        }
+    }
+
+    fn step_frame(&mut self) {
+        if self.finished() { return; }
+        let inputs = self.replay.inputs_at(self.tick as usize).unwrap_or_default();
+        self.tick += 1;
+        let players = (self.replay.players as usize).clamp(1, inputs.len());
+        self.sim.step(&inputs[..players]);
+        if self.tick as usize >= self.replay.frames.len() {
+            self.sim.end_run();
+        }
+    }
+
+    /// Whether the ghost's run has ended
+    pub fn finished(&self) -> bool {
+        !self.sim.in_run()
+    }
+
+    /// The ghost's score so far
+    pub fn score(&self) -> u32 {
+        self.sim.score
+    }
+
+    /// Live score minus the ghost's: ahead when positive
+    pub fn delta(&self, score: u32) -> i64 {
+        score as i64 - self.score() as i64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::Input;
+    use crate::types::RunMode;
+
+    #[test]
+    fn typed_hex_imports_as_a_replay() {
+        let mut replay = Replay::new(99, RunMode::Endless, 1, false);
+        let mut sim = replay.start();
+        for tick in 0..120 {
+            let inputs = [Input { start: tick == 0, drop: tick % 30 == 0, ..Input::default() }];
+            replay.record(&inputs);
+            sim.step(&inputs);
+        }
+        replay.finish(&sim);
+
+        // Line breaks, spaces and capitals from a pasted copy are all fine
+        let mut import = GhostImport::default();
+        for line in replay.to_hex().to_uppercase().as_bytes().chunks(40) {
+            let chars: Vec<char> = line.iter().map(|&b| b as char).chain([' ', '\n']).collect();
+            import.type_chars(&chars);
+        }
+        assert_eq!(import.hex, replay.to_hex());
+        let imported = import.finish().expect("typed ghost should load");
+        assert_eq!(imported.seed, replay.seed);
+        assert_eq!(imported.mode, replay.mode);
+        assert_eq!(imported.frames, replay.frames);
+        assert!(import.error.is_none());
+    }
+
+    #[test]
+    fn a_bad_import_says_why_until_it_is_edited() {
+        let mut import = GhostImport::default();
+        import.type_chars(&['a', 'b', 'c']);
+        assert!(import.finish().is_none());
+        assert_eq!(import.error.as_deref(), Some("replay text is not valid hex"));
+
+        import.backspace();
+        assert_eq!(import.hex, "ab");
+        assert!(import.error.is_none());
+        assert!(import.finish().is_none());
+        assert_eq!(import.error.as_deref(), Some("replay data is truncated"));
+    }
+}
@@ -0,0 +1,396 @@
+// LEVEL AND DIFFICULTY TABLE
+//
+// Per-level tuning is read from levels.toml (embedded at build time) so it can
+// be rebalanced without touching the game code. Missing fields inherit from the
+// previous level, and out-of-range values fall back with a warning instead of
+// breaking the game.
+
+use crate::patterns::PatternLibrary;
+use crate::tuning::pick;
+use crate::types::{EnemyKind, PowerUpKind};
+use std::collections::BTreeMap;
+use turbo::serde::Deserialize;
+
+pub const LEVELS_TOML: &str = include_str!("../levels.toml");
+
+/// Tuning for one level
+#[turbo::serialize]
+pub struct LevelConfig {
+    pub scroll_speed: f32,
+    pub chimney_spacing: (f32, f32),  // Min/max gap between chimneys
+    pub krampus_interval: u32,        // Frames between attacks
+    pub krampus_duration: u32,        // Frames an attack lasts
+    pub krampus_health: u32,          // Snowball hits to drive him off early
+    pub fire_rate: u32,               // Frames between volleys
+    pub projectile_speed: f32,
+    pub patterns: Vec<usize>,         // Indices into the pattern library
+    pub stage2_patterns: Vec<usize>,  // Once Krampus is down to two thirds health (empty = previous stage's)
+    pub stage3_patterns: Vec<usize>,  // His last third
+    pub sky_color: u32,
+    pub powerup_interval: (u32, u32), // Min/max frames between power-ups
+    pub powerup_weights: Vec<(PowerUpKind, u32)>,  // Relative spawn chances
+    pub enemies: Vec<EnemyKind>,      // Roster to spawn from; repeat a kind to make it likelier
+    pub enemy_interval: (u32, u32),   // Min/max frames between enemies
+    pub wind: f32,                    // Steady push on gifts and snow (px/frame², + blows right)
+    pub gust: f32,                    // How far the wind swings either side of `wind`
+    pub chimney_hitbox: f32,          // Half-width of a chimney's catch area
+    pub request_chance: f32,          // Chance a house asks for a particular gift
+    pub naughty_chance: f32,          // Chance a house is on the naughty list
+    pub visited_chance: f32,          // Chance a house already had its presents
+}
+
+impl LevelConfig {
+    /// Patterns Krampus picks from at a boss stage (1-3)
+    pub fn patterns_for(&self, stage: u8) -> &[usize] {
+        match stage {
+            3 if !self.stage3_patterns.is_empty() => &self.stage3_patterns,
+            2 | 3 if !self.stage2_patterns.is_empty() => &self.stage2_patterns,
+            _ => &self.patterns,
+        }
+    }
+}
+
+impl Default for LevelConfig {
+    fn default() -> Self {
+        Self {
+            scroll_speed: 1.5,
+            chimney_spacing: (120.0, 200.0),
+            krampus_interval: 560,
+            krampus_duration: 360,
+            krampus_health: 8,
+            fire_rate: 45,
+            projectile_speed: 2.8,
+            patterns: vec![0],
+            stage2_patterns: vec![],
+            stage3_patterns: vec![],
+            sky_color: 0x1a2744ff,
+            powerup_interval: (900, 1500),
+            powerup_weights: vec![(PowerUpKind::Health, 2), (PowerUpKind::Star, 1)],
+            enemies: vec![],
+            enemy_interval: (600, 900),
+            wind: 0.0,
+            gust: 0.0,
+            chimney_hitbox: 25.0,
+            request_chance: 0.0,
+            naughty_chance: 0.0,
+            visited_chance: 0.0,
+        }
+    }
+}
+
+/// The full difficulty curve of a run
+#[turbo::serialize]
+pub struct LevelTable {
+    pub deliveries_per_level: u32,
+    pub first_krampus_delay: u32,
+    pub first_powerup_delay: u32,
+    pub levels: Vec<LevelConfig>,
+    pub patterns: PatternLibrary,
+}
+
+impl Default for LevelTable {
+    fn default() -> Self {
+        Self {
+            deliveries_per_level: 5,
+            first_krampus_delay: 1200,
+            first_powerup_delay: 600,
+            levels: vec![LevelConfig::default()],
+            patterns: PatternLibrary::fallback(),
+        }
+    }
+}
+
+// Raw file shape: every field optional so levels can inherit
+#[derive(Deserialize, Default)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawTable {
+    deliveries_per_level: Option<u32>,
+    first_krampus_delay: Option<u32>,
+    first_powerup_delay: Option<u32>,
+    level: Vec<RawLevel>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawLevel {
+    scroll_speed: Option<f32>,
+    chimney_spacing: Option<(f32, f32)>,
+    krampus_interval: Option<u32>,
+    krampus_duration: Option<u32>,
+    krampus_health: Option<u32>,
+    fire_rate: Option<u32>,
+    projectile_speed: Option<f32>,
+    patterns: Option<Vec<String>>,
+    stage2_patterns: Option<Vec<String>>,
+    stage3_patterns: Option<Vec<String>>,
+    sky_color: Option<u32>,
+    powerup_interval: Option<(u32, u32)>,
+    powerups: Option<BTreeMap<String, u32>>,
+    enemies: Option<Vec<EnemyKind>>,
+    enemy_interval: Option<(u32, u32)>,
+    wind: Option<f32>,
+    gust: Option<f32>,
+    chimney_hitbox: Option<f32>,
+    request_chance: Option<f32>,
+    naughty_chance: Option<f32>,
+    visited_chance: Option<f32>,
+}
+
+/// Look up pattern names, dropping unknown ones; `None` keeps `fallback`
+fn resolve_patterns(
+    warnings: &mut Vec<String>,
+    level: usize,
+    name: &str,
+    value: Option<Vec<String>>,
+    fallback: &[usize],
+    library: &PatternLibrary,
+    allow_empty: bool,
+) -> Vec<usize> {
+    let Some(names) = value else { return fallback.to_vec() };
+    let mut found = vec![];
+    for pattern in &names {
+        match library.find(pattern) {
+            Some(i) => found.push(i),
+            None => warnings.push(format!("levels.toml: level {level}: {name} has unknown pattern \"{pattern}\", skipping it")),
+        }
+    }
+    if found.is_empty() && !(allow_empty && names.is_empty()) {
+        warnings.push(format!("levels.toml: level {level}: {name} has no usable patterns, keeping the previous level's"));
+        return fallback.to_vec();
+    }
+    found
+}
+
+/// Turn a `powerups = { name = weight }` table into weights, dropping unknown
+/// names; `None` or an all-zero table keeps `fallback`
+fn resolve_powerups(
+    warnings: &mut Vec<String>,
+    level: usize,
+    value: Option<BTreeMap<String, u32>>,
+    fallback: &[(PowerUpKind, u32)],
+) -> Vec<(PowerUpKind, u32)> {
+    let Some(table) = value else { return fallback.to_vec() };
+    let mut weights = vec![];
+    for (name, weight) in table {
+        match PowerUpKind::ALL.iter().find(|k| k.name() == name) {
+            Some(&kind) if weight > 0 => weights.push((kind, weight)),
+            Some(_) => {}
+            None => warnings.push(format!("levels.toml: level {level}: powerups has unknown power-up \"{name}\", skipping it")),
+        }
+    }
+    if weights.is_empty() {
+        warnings.push(format!("levels.toml: level {level}: powerups has no usable weights, keeping the previous level's"));
+        return fallback.to_vec();
+    }
+    weights
+}
+
+impl LevelTable {
+    /// The table built into the game, or the defaults if it fails to parse
+    pub fn embedded() -> Self {
+        Self::parse(LEVELS_TOML, PatternLibrary::embedded()).0
+    }
+
+    /// Parse and validate a level table against a pattern library, returning
+    /// any problems found. Never fails: anything unusable is replaced with a
+    /// sane fallback.
+    pub fn parse(text: &str, library: PatternLibrary) -> (Self, Vec<String>) {
+        let mut warnings = vec![];
+        let raw: RawTable = match toml::from_str(text) {
+            Ok(raw) => raw,
+            Err(err) => {
+                warnings.push(format!("levels.toml: {err}; using built-in defaults"));
+                return (Self::with_library(library), warnings);
+            }
+        };
+
+        let defaults = Self::with_library(library);
+        let mut table = Self {
+            deliveries_per_level: pick(&mut warnings, "levels.toml", "", "deliveries_per_level", raw.deliveries_per_level, defaults.deliveries_per_level, |v| v > 0),
+            first_krampus_delay: pick(&mut warnings, "levels.toml", "", "first_krampus_delay", raw.first_krampus_delay, defaults.first_krampus_delay, |v| v >= 60),
+            first_powerup_delay: raw.first_powerup_delay.unwrap_or(defaults.first_powerup_delay),
+            levels: Vec::with_capacity(raw.level.len()),
+            patterns: defaults.patterns,
+        };
+
+        let mut prev = defaults.levels[0].clone();
+        for (i, raw) in raw.level.into_iter().enumerate() {
+            let n = i + 1;
+            let place = format!("level {n}");
+            let w = &mut warnings;
+            let lib = &table.patterns;
+            let patterns = resolve_patterns(w, n, "patterns", raw.patterns, &prev.patterns, lib, false);
+            let stage2_patterns = resolve_patterns(w, n, "stage2_patterns", raw.stage2_patterns, &prev.stage2_patterns, lib, true);
+            let stage3_patterns = resolve_patterns(w, n, "stage3_patterns", raw.stage3_patterns, &prev.stage3_patterns, lib, true);
+            let mut enemies = raw.enemies.unwrap_or_else(|| prev.enemies.clone());
+            if enemies.contains(&EnemyKind::Krampus) {
+                w.push(format!("levels.toml: level {n}: enemies can't include krampus (he comes on krampus_interval), skipping it"));
+                enemies.retain(|&e| e != EnemyKind::Krampus);
+            }
+            let level = LevelConfig {
+                scroll_speed: pick(w, "levels.toml", &place, "scroll_speed", raw.scroll_speed, prev.scroll_speed, |v| (0.5..=8.0).contains(&v)),
+                chimney_spacing: pick(w, "levels.toml", &place, "chimney_spacing", raw.chimney_spacing, prev.chimney_spacing, |(lo, hi)| lo >= 60.0 && hi >= lo && hi < 1000.0),
+                krampus_interval: pick(w, "levels.toml", &place, "krampus_interval", raw.krampus_interval, prev.krampus_interval, |v| v >= 60),
+                krampus_duration: pick(w, "levels.toml", &place, "krampus_duration", raw.krampus_duration, prev.krampus_duration, |v| (60..=3600).contains(&v)),
+                krampus_health: pick(w, "levels.toml", &place, "krampus_health", raw.krampus_health, prev.krampus_health, |v| (1..=200).contains(&v)),
+                fire_rate: pick(w, "levels.toml", &place, "fire_rate", raw.fire_rate, prev.fire_rate, |v| v >= 5),
+                projectile_speed: pick(w, "levels.toml", &place, "projectile_speed", raw.projectile_speed, prev.projectile_speed, |v| (0.5..=10.0).contains(&v)),
+                patterns,
+                stage2_patterns,
+                stage3_patterns,
+                sky_color: raw.sky_color.unwrap_or(prev.sky_color),
+                powerup_interval: pick(w, "levels.toml", &place, "powerup_interval", raw.powerup_interval, prev.powerup_interval, |(lo, hi)| lo >= 60 && hi >= lo),
+                powerup_weights: resolve_powerups(w, n, raw.powerups, &prev.powerup_weights),
+                enemies,
+                enemy_interval: pick(w, "levels.toml", &place, "enemy_interval", raw.enemy_interval, prev.enemy_interval, |(lo, hi)| lo >= 30 && hi >= lo),
+                wind: pick(w, "levels.toml", &place, "wind", raw.wind, prev.wind, |v| (-0.1..=0.1).contains(&v)),
+                gust: pick(w, "levels.toml", &place, "gust", raw.gust, prev.gust, |v| (0.0..=0.1).contains(&v)),
+                chimney_hitbox: pick(w, "levels.toml", &place, "chimney_hitbox", raw.chimney_hitbox, prev.chimney_hitbox, |v| (6.0..=40.0).contains(&v)),
+                request_chance: pick(w, "levels.toml", &place, "request_chance", raw.request_chance, prev.request_chance, |v| (0.0..=1.0).contains(&v)),
+                naughty_chance: pick(w, "levels.toml", &place, "naughty_chance", raw.naughty_chance, prev.naughty_chance, |v| (0.0..=0.5).contains(&v)),
+                visited_chance: pick(w, "levels.toml", &place, "visited_chance", raw.visited_chance, prev.visited_chance, |v| (0.0..=0.5).contains(&v)),
+            };
+            prev = level.clone();
+            table.levels.push(level);
+        }
+
+        if table.levels.is_empty() {
+            warnings.push("levels.toml: no [[level]] entries; using built-in defaults".to_string());
+            table.levels = defaults.levels;
+        }
+        (table, warnings)
+    }
+
+    /// The default curve, firing the library's plain aimed shot (or its first pattern)
+    fn with_library(patterns: PatternLibrary) -> Self {
+        let aimed = patterns.find("aimed").unwrap_or(0);
+        let mut table = Self { patterns, ..Self::default() };
+        table.levels[0].patterns = vec![aimed];
+        table
+    }
+
+    /// Settings for a 1-based level; levels past the table reuse the last entry
+    pub fn get(&self, level: u32) -> &LevelConfig {
+        let i = (level.max(1) as usize - 1).min(self.levels.len() - 1);
+        &self.levels[i]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> (LevelTable, Vec<String>) {
+        LevelTable::parse(text, PatternLibrary::embedded())
+    }
+
+    #[test]
+    fn the_embedded_table_parses_cleanly() {
+        let (table, warnings) = LevelTable::parse(LEVELS_TOML, PatternLibrary::embedded());
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(table.levels.len() > 1);
+    }
+
+    #[test]
+    fn levels_inherit_what_they_leave_out() {
+        let (table, warnings) = parse(r#"
+            [[level]]
+            scroll_speed = 2.0
+            krampus_health = 12
+            patterns = ["spread"]
+            powerups = { star = 3 }
+            enemies = ["minion"]
+
+            [[level]]
+            scroll_speed = 3.0
+        "#);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let (first, second) = (table.get(1), table.get(2));
+        assert_eq!(second.scroll_speed, 3.0);
+        assert_eq!(second.krampus_health, 12);
+        assert_eq!(second.patterns, first.patterns);
+        assert_eq!(second.powerup_weights, vec![(PowerUpKind::Star, 3)]);
+        assert_eq!(second.enemies, vec![EnemyKind::Minion]);
+
+        // Past the end of the table the last level repeats
+        assert_eq!(table.get(9).scroll_speed, 3.0);
+        // The first level inherits from the built-in defaults
+        assert_eq!(first.fire_rate, LevelConfig::default().fire_rate);
+    }
+
+    #[test]
+    fn unknown_pattern_and_power_up_names_are_skipped() {
+        let (table, warnings) = parse(r#"
+            [[level]]
+            patterns = ["spread", "laser"]
+            powerups = { health = 1, jetpack = 5 }
+
+            [[level]]
+            patterns = ["laser"]
+            powerups = { jetpack = 5 }
+        "#);
+        let library = PatternLibrary::embedded();
+        let spread = library.find("spread").unwrap();
+        assert_eq!(table.get(1).patterns, vec![spread]);
+        assert_eq!(table.get(1).powerup_weights, vec![(PowerUpKind::Health, 1)]);
+
+        // Nothing usable left keeps the previous level's
+        assert_eq!(table.get(2).patterns, vec![spread]);
+        assert_eq!(table.get(2).powerup_weights, vec![(PowerUpKind::Health, 1)]);
+        assert_eq!(warnings, [
+            "levels.toml: level 1: patterns has unknown pattern \"laser\", skipping it",
+            "levels.toml: level 1: powerups has unknown power-up \"jetpack\", skipping it",
+            "levels.toml: level 2: patterns has unknown pattern \"laser\", skipping it",
+            "levels.toml: level 2: patterns has no usable patterns, keeping the previous level's",
+            "levels.toml: level 2: powerups has unknown power-up \"jetpack\", skipping it",
+            "levels.toml: level 2: powerups has no usable weights, keeping the previous level's",
+        ]);
+    }
+
+    #[test]
+    fn out_of_range_values_keep_the_previous_level() {
+        let (table, warnings) = parse(r#"
+            deliveries_per_level = 0
+
+            [[level]]
+            scroll_speed = 2.0
+            chimney_spacing = [150.0, 100.0]
+
+            [[level]]
+            scroll_speed = 50.0
+            wind = 1.0
+        "#);
+        assert_eq!(table.deliveries_per_level, LevelTable::default().deliveries_per_level);
+        assert_eq!(table.get(1).chimney_spacing, LevelConfig::default().chimney_spacing);
+        assert_eq!(table.get(2).scroll_speed, 2.0);
+        assert_eq!(table.get(2).wind, 0.0);
+        assert_eq!(warnings, [
+            "levels.toml: deliveries_per_level = 0 is out of range, using 5",
+            "levels.toml: level 1: chimney_spacing = (150.0, 100.0) is out of range, using (120.0, 200.0)",
+            "levels.toml: level 2: scroll_speed = 50.0 is out of range, using 2.0",
+            "levels.toml: level 2: wind = 1.0 is out of range, using 0.0",
+        ]);
+    }
+
+    #[test]
+    fn krampus_is_not_a_scheduled_enemy() {
+        let (table, warnings) = parse(r#"
+            [[level]]
+            enemies = ["minion", "krampus", "snow_golem"]
+        "#);
+        assert_eq!(table.get(1).enemies, vec![EnemyKind::Minion, EnemyKind::SnowGolem]);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("enemies can't include krampus"));
+    }
+
+    #[test]
+    fn a_broken_file_falls_back_to_the_defaults() {
+        for text in ["[[level]\nscroll_speed = 2.0", "[[level]]\nspeed = 2.0", "[[level]]\nenemies = [\"yeti\"]", ""] {
+            let (table, warnings) = parse(text);
+            assert_eq!(warnings.len(), 1, "{text:?}: {warnings:?}");
+            assert_eq!(table.levels.len(), 1);
+            assert_eq!(table.get(1).scroll_speed, LevelConfig::default().scroll_speed);
+            assert_eq!(table.get(1).patterns, vec![table.patterns.find("aimed").unwrap()]);
+        }
+    }
+}
@@ -2,62 +2,84 @@ use turbo::*;
 
 // MODULES
 
-mod types;
-mod sound;
+pub mod types;
+pub mod sound;
+pub mod tuning;
+pub mod levels;
+pub mod patterns;
+pub mod town;
+pub mod campaign;
+pub mod daily;
+pub mod sim;
+pub mod replay;
+pub mod ghost;
+pub mod save;
+pub mod assets;
 
 use types::*;
+use levels::{LevelTable, LEVELS_TOML};
+use patterns::{PatternLibrary, PATTERNS_TOML};
+use town::{TownPlan, TOWN_TOML};
+use campaign::{Campaign, CAMPAIGN_TOML};
+use sim::{Event, Input, Simulation};
+use replay::{Replay, RunSummary};
+use ghost::{Ghost, GhostImport, GhostSource};
+use save::{SaveData, ScoreEntry};
+use sound::{AudioManager, Sfx};
 
 
+// Rows of the options screen
+const OPTIONS: [&str; 9] = ["Music", "Sound FX", "Screen shake", "Flash", "Aim guide", "Players", "Lives", "Reset tutorial", "Back"];
+
+/// Glow and timer-bar colour for each power-up
+fn powerup_color(kind: PowerUpKind) -> u32 {
+    match kind {
+        PowerUpKind::Health => 0x44ff44ff,
+        PowerUpKind::Star => COLOR_STAR,
+        PowerUpKind::MultiGift => 0xff4444ff,
+        PowerUpKind::Magnet => 0xdd2222ff,
+        PowerUpKind::SlowMo => 0x88ccffff,
+        PowerUpKind::Multiplier => COLOR_GOLD,
+        PowerUpKind::Shield => 0x66ccffff,
+        PowerUpKind::ComboExtend => 0xff00ffff,
+    }
+}
+
 #[turbo::game]
 struct GameState {
     frame: u32,
-    mode: u8,  // 0=Title, 1=Delivering, 2=KrampusAttack, 3=GameOver, 4=Paused
-    previous_mode: u8,  // For pause/resume
-    
-    // Scrolling
-    scroll_x: f32,
-    scroll_speed: f32,
-    base_scroll_speed: f32,  // For progressive difficulty
-    
-    // Player (Santa's sleigh)
-    player_y: f32,
-    player_vel_y: f32,
-    sleigh_tilt: f32,
-    
-    // Chimneys (delivery targets)
-    chimneys: Vec<Chimney>,
-    next_chimney_spawn: f32,
-    
-    // Falling gifts
-    gifts: Vec<FallingGift>,
-    
-    // Krampus
-    krampus_x: f32,
-    krampus_y: f32,
-    krampus_active: bool,
-    krampus_attack_timer: u32,
-    krampus_duration: u32,
-    projectiles: Vec<Projectile>,
-    krampus_warning: u32,
-    
-    // Stats
-    health: u32,
-    score: u32,
-    high_score: u32,
-    deliveries: u32,
-    naughty_meter: u32,
-    level: u32,
-    
-    // Combo system
-    combo_count: u32,
-    combo_timer: u32,  // Frames until combo resets
-    max_combo: u32,
+    
+    // Gameplay (pure logic, see sim.rs)
+    sim: Simulation,
+    
+    // Fixed timestep
+    last_update_ms: u64,   // Wall clock at the previous update, 0 before the first
+    tick_accumulator: u64, // Unspent time in ms * TICK_RATE (1000 = one tick)
+    pending_input: [Input; MAX_PLAYERS],  // Presses seen since the last tick, so none fall between ticks
+    render_alpha: f32,     // How far between the last two ticks to draw (0..1)
+    
+    // Persistent settings, stats and scores (see save.rs)
+    save: SaveData,
+    new_high_score: bool,
+    night_result: Option<NightResult>,  // Set when a campaign night ends
+    daily_share: Option<String>,        // Result line of the last daily challenge run
+    new_daily_best: bool,
+    run_krampus_survived: u32,  // Attacks survived in the current run, for lifetime stats
+    
+    // Menus and leaderboard entry
+    menu: Menu,
+    initials_entry: Option<InitialsEntry>,  // Set while typing initials on game over
+    last_initials: [u8; 3],                 // Pre-filled for the next entry
+    highlight_rank: Option<usize>,          // Leaderboard row of the latest run
+    options_cursor: usize,
+    tutorial_reset: bool,                   // Show confirmation on the options screen
     
     // Visual effects
     screen_flash: u32,
     flash_color: u32,
     screen_shake: u32,
-    invincible_timer: u32,
+    krampus_hit_flash: u32,  // Krampus blinks white after a snowball hit
+    attack_notice: Option<(u8, bool, u32)>,  // Versus: who sent an attack, whether it was a naughty house, ticks left on screen
     
     // Screen transitions
     fade_alpha: u32,      // 0-255 for fade effect
@@ -66,76 +88,76 @@ struct GameState {
     // Particles
     snowflakes: Vec<Snowflake>,
     particles: Vec<Particle>,
-    
-    // Power-ups
-    powerups: Vec<PowerUp>,
-    powerup_spawn_timer: u32,
-    star_power_timer: u32,  // Invincibility from star power-up
+    grade_popups: Vec<GradePopup>,
     
     // Tutorial
     tutorial_timer: u32,
-    first_play: bool,
     tutorial_step: u8,
     
-    // Audio settings
-    music_volume: f32,  // 0.0 to 1.0
-    sfx_volume: f32,    // 0.0 to 1.0
-    
-    // RNG
-    rng_seed: u32,
+    // Replays
+    recording: Replay,            // Input of the run in progress
+    last_replay: Option<Replay>,  // Most recent finished run
+    playback: Option<Replay>,     // Replay being watched instead of live input
+    playback_tick: u32,
+    replay_verified: Option<bool>,
+    
+    // Ghost racing (see ghost.rs)
+    ghost_source: GhostSource,      // Picked on the title screen for the next run
+    ghost: Option<Ghost>,           // Run being raced alongside this one
+    imported_ghost: Option<Replay>, // Someone else's run, imported on the title screen
+    ghost_import: Option<GhostImport>,  // Set while typing or pasting in a ghost
+    new_ghost: bool,                // The last run became the personal best ghost
+    
+    // Music and sound effects (see sound.rs)
+    sound: AudioManager,
+    
+    // Cosmetic RNG, kept apart from the gameplay seed so effects never change a run
+    fx_rng: Rng,
 }
 
-// Game mode constants
-
-
-// Screen dimensions (larger for better resolution)
-// Constants imported from types.rs
-
 impl GameState {
     pub fn new() -> Self {
+        let (patterns, mut warnings) = PatternLibrary::parse(PATTERNS_TOML);
+        let (levels, level_warnings) = LevelTable::parse(LEVELS_TOML, patterns);
+        warnings.extend(level_warnings);
+        let (town, town_warnings) = TownPlan::parse(TOWN_TOML);
+        warnings.extend(town_warnings);
+        let (campaign, campaign_warnings) = Campaign::parse(CAMPAIGN_TOML);
+        warnings.extend(campaign_warnings);
+        for warning in &warnings {
+            log!("{}", warning);
+        }
+        
         let mut state = Self {
             frame: 0,
-            mode: MODE_TITLE,
-            previous_mode: MODE_TITLE,
             
-            scroll_x: 0.0,
-            scroll_speed: 1.5,
-            base_scroll_speed: 1.5,
+            sim: Simulation::with_levels(42, levels),
             
-            player_y: SCREEN_H / 2.0,
-            player_vel_y: 0.0,
-            sleigh_tilt: 0.0,
+            last_update_ms: 0,
+            tick_accumulator: 0,
+            pending_input: [Input::default(); MAX_PLAYERS],
+            render_alpha: 1.0,
             
-            chimneys: vec![],
-            next_chimney_spawn: 100.0,
+            save: SaveData::default(),
+            new_high_score: false,
+            night_result: None,
+            daily_share: None,
+            new_daily_best: false,
+            run_krampus_survived: 0,
             
-            gifts: vec![],
-            
-            krampus_x: SCREEN_W + 50.0,
-            krampus_y: SCREEN_H / 2.0,
-            krampus_active: false,
-            krampus_attack_timer: 1200,
-            krampus_duration: 0,
-            projectiles: vec![],
-            krampus_warning: 0,
-            
-            health: 3,
-            score: 0,
-            high_score: 0,
-            deliveries: 0,
-            naughty_meter: 0,
-            level: 1,
-            
-            // Combo system
-            combo_count: 0,
-            combo_timer: 0,
-            max_combo: 0,
+            menu: Menu::None,
+            initials_entry: None,
+            last_initials: *b"AAA",
+            highlight_rank: None,
+            options_cursor: 0,
+            tutorial_reset: false,
             
             // Visual effects
             screen_flash: 0,
             flash_color: 0xffffffff,
             screen_shake: 0,
-            invincible_timer: 0,
+            krampus_hit_flash: 0,
+            attack_notice: None,
             
             // Screen transitions
             fade_alpha: 255,
@@ -144,90 +166,53 @@ impl GameState {
             // Particles
             snowflakes: vec![],
             particles: vec![],
-            
-            // Power-ups
-            powerups: vec![],
-            powerup_spawn_timer: 600,  // First power-up after 10 seconds
-            star_power_timer: 0,
+            grade_popups: vec![],
             
             // Tutorial
             tutorial_timer: 0,
-            first_play: true,
             tutorial_step: 0,
             
-            // Audio settings (default full volume)
-            music_volume: 1.0,
-            sfx_volume: 1.0,
+            recording: Replay::new(42, RunMode::Classic, 1, false),
+            last_replay: None,
+            playback: None,
+            playback_tick: 0,
+            replay_verified: None,
+            
+            ghost_source: GhostSource::Off,
+            ghost: None,
+            imported_ghost: None,
+            ghost_import: None,
+            new_ghost: false,
+            
+            sound: AudioManager::default(),
             
-            rng_seed: 42,
+            fx_rng: Rng::new(7),
         };
+        state.sim.town = town;
+        state.sim.campaign = campaign;
         state.init_snowflakes();
-        state.load_high_score();
+        state.load_save();
+        state.sound.play_music(state.sim.mode.music());
         state
     }
     
-    // RANDOM
-    
-    fn random(&mut self) -> u32 {
-        self.rng_seed = self.rng_seed.wrapping_mul(1103515245).wrapping_add(12345);
-        (self.rng_seed >> 16) & 0x7FFF
-    }
-    
-    fn random_range(&mut self, min: f32, max: f32) -> f32 {
-        let r = (self.random() % 1000) as f32 / 1000.0;
-        min + r * (max - min)
-    }
-    
     // AUDIO SYSTEM
     
-    /// Play background music based on current game mode
-    fn play_mode_music(&self) {
-        // Stop all music tracks first
-        audio::stop("start");
-        audio::stop("game");
-        audio::stop("krampus");
-        audio::stop("game_over");
-        
-        // Play appropriate track for current mode
-        match self.mode {
-            MODE_TITLE => audio::play("start"),
-            MODE_DELIVERING => audio::play("game"),
-            MODE_KRAMPUS => audio::play("krampus"),
-            MODE_GAMEOVER => audio::play("game_over"),
-            _ => {}
-        }
-    }
-    
-    /// Keep music looping (call every frame)
-    fn update_music(&self) {
-        match self.mode {
-            MODE_TITLE => {
-                if !audio::is_playing("start") {
-                    audio::play("start");
-                }
-            }
-            MODE_DELIVERING => {
-                if !audio::is_playing("game") {
-                    audio::play("game");
-                }
-            }
-            MODE_KRAMPUS => {
-                if !audio::is_playing("krampus") {
-                    audio::play("krampus");
-                }
-            }
-            MODE_GAMEOVER => {
-                if !audio::is_playing("game_over") {
-                    audio::play("game_over");
-                }
-            }
-            _ => {}
-        }
+    /// Push the saved volume settings into the audio manager
+    fn apply_volumes(&mut self) {
+        let settings = self.save.settings;
+        self.sound.set_volumes(settings.music_volume, settings.sfx_volume);
     }
     
-    /// Play a one-shot sound effect
-    fn play_sfx(name: &str) {
-        audio::play(name);
+    /// Use the saved co-op settings for the next run (never mid-run, so the
+    /// recording keeps the players it started with)
+    fn apply_players(&mut self) {
+        if self.sim.in_run() { return; }
+        self.sim.player_count = match self.sim.run_mode {
+            RunMode::Versus => MAX_PLAYERS as u8,
+            _ => self.save.settings.players,
+        };
+        self.sim.shared_lives = self.save.settings.shared_lives;
     }
     
     // INITIALIZATION
@@ -236,749 +221,716 @@ impl GameState {
         self.snowflakes.clear();
         // More snowflakes for larger screen
         for _ in 0..50 {
-            let x = self.random_range(0.0, SCREEN_W);
-            let y = self.random_range(0.0, SCREEN_H);
-            let speed = self.random_range(0.5, 1.8);
-            let size = (self.random() % 3 + 1) as u32;
+            let x = self.fx_rng.range(0.0, SCREEN_W);
+            let y = self.fx_rng.range(0.0, SCREEN_H);
+            let speed = self.fx_rng.range(0.5, 1.8);
+            let size = self.fx_rng.next_u32() % 3 + 1;
             self.snowflakes.push(Snowflake { x, y, speed, size });
         }
     }
     
-    fn start_game(&mut self) {
-        self.mode = MODE_DELIVERING;
-        self.scroll_x = 0.0;
-        self.scroll_speed = 1.5;
-        self.base_scroll_speed = 1.5;
-        self.player_y = SCREEN_H / 2.0;
-        self.player_vel_y = 0.0;
-        self.sleigh_tilt = 0.0;
-        
-        self.chimneys.clear();
-        self.next_chimney_spawn = 150.0;
-        self.gifts.clear();
-        self.projectiles.clear();
-        self.particles.clear();
-        self.powerups.clear();
-        
-        self.krampus_active = false;
-        self.krampus_attack_timer = 1200;
-        self.krampus_warning = 0;
-        self.krampus_duration = 0;
-        
-        self.health = 3;
-        self.score = 0;
-        self.deliveries = 0;
-        self.naughty_meter = 0;
-        self.level = 1;
-        self.invincible_timer = 0;
-        self.star_power_timer = 0;
-        
-        // Combo system
-        self.combo_count = 0;
-        self.combo_timer = 0;
-        self.max_combo = 0;
-        
-        // Power-ups
-        self.powerup_spawn_timer = 600;
-        
-        // Screen transition (fade in)
-        self.start_fade_in();
-        
-        // Tutorial
-        if self.first_play {
-            self.tutorial_timer = 600;
-            self.tutorial_step = 0;
-        } else {
-            self.tutorial_timer = 0;
+    // ========================================================================
+    // INPUT
+    // ========================================================================
+    
+    /// Sample a player's Turbo gamepad into a simulation input. Player 1 also
+    /// has the keyboard; player 2 flies with the second gamepad.
+    fn read_input(player: usize) -> Input {
+        let gp = gamepad::get(player);
+        let mut input = Input {
+            up: gp.up.pressed(),
+            down: gp.down.pressed(),
+            // Drop with the A/B button
+            drop: gp.a.just_pressed() || gp.b.just_pressed(),
+            pause: false,
+            start: gp.start.just_pressed() || gp.a.just_pressed(),
+            // Next gift type with right/X on the gamepad
+            cycle: gp.right.just_pressed() || gp.x.just_pressed(),
+        };
+        if player == 0 {
+            // Drop with Enter or Space, pause with Escape, next gift with Tab
+            let kb = keyboard::get();
+            input.drop |= kb.enter().just_pressed() || kb.space().just_pressed();
+            input.pause = kb.escape().just_pressed();
+            input.start |= kb.enter().just_pressed();
+            input.cycle |= kb.tab().just_pressed();
+        }
+        input
+    }
+    
+    /// Fold this frame's controls into the input for the next tick: held buttons
+    /// follow the latest frame, presses stick until a tick consumes them
+    fn latch_input(&mut self) {
+        for (player, pending) in self.pending_input.iter_mut().enumerate() {
+            let live = Self::read_input(player);
+            pending.up = live.up;
+            pending.down = live.down;
+            pending.drop |= live.drop;
+            pending.pause |= live.pause;
+            pending.start |= live.start;
+            pending.cycle |= live.cycle;
         }
-        
-        self.screen_flash = 8;
-        self.flash_color = 0xffffffff;
-        
-        // Start game music
-        self.play_mode_music();
     }
     
-    fn reset_game(&mut self) {
-        if self.score > self.high_score {
-            self.high_score = self.score;
-            self.save_high_score();
+    /// Next input for each player: the replay being watched, or the live controls
+    fn next_input(&mut self) -> [Input; MAX_PLAYERS] {
+        match &self.playback {
+            Some(replay) => {
+                let inputs = replay.inputs_at(self.playback_tick as usize).unwrap_or_default();
+                self.playback_tick += 1;
+                inputs
+            }
+            None => {
+                let inputs = self.pending_input;
+                self.pending_input = inputs.map(|i| Input { up: i.up, down: i.down, ..Input::default() });
+                inputs
+            }
         }
-        self.first_play = false;
-        self.start_game();
     }
     
     // ========================================================================
-    // HIGH SCORE PERSISTENCE
+    // EVENT HANDLING
     // ========================================================================
     
-    fn load_high_score(&mut self) {
-        // Try to load high score from local storage
-        if let Ok(data) = local::load() {
-            if data.len() >= 4 {
-                self.high_score = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+    /// Turn simulation events into sound, flashes, shakes and particles
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::ModeChanged { from, to } => {
+                self.on_exit(from, to);
+                self.on_enter(to, from);
+            }
+            Event::GameStarted => {
+                self.particles.clear();
+                self.grade_popups.clear();
+                self.new_high_score = false;
+                self.night_result = None;
+                self.daily_share = None;
+                self.new_daily_best = false;
+                self.attack_notice = None;
+                self.run_krampus_survived = 0;
+                self.initials_entry = None;
+                self.highlight_rank = None;
+                self.recording = Replay::new(self.sim.rng.seed, self.sim.run_mode, self.sim.player_count, self.sim.shared_lives);
+                self.new_ghost = false;
+                self.ghost = if self.playback.is_none() { self.ghost_replay().map(Ghost::new) } else { None };
+                
+                // Tutorial
+                if self.save.first_play {
+                    self.tutorial_timer = 600;
+                    self.tutorial_step = 0;
+                } else {
+                    self.tutorial_timer = 0;
+                }
+                
+                self.screen_flash = 8;
+                self.flash_color = 0xffffffff;
+            }
+            Event::GiftDropped => {
+                // Visual feedback that gift was dropped
+                self.screen_flash = 2;
+                self.flash_color = 0xffffff44;
+            }
+            Event::Delivered { x, y, requested, grade, .. } => {
+                self.screen_flash = 4;
+                self.flash_color = 0x00ff00ff;
+                match grade {
+                    HitGrade::Perfect => {
+                        self.screen_flash = 6;
+                        self.flash_color = COLOR_GOLD;
+                        self.spawn_delivery_particles(x, y);
+                        self.spawn_star_particles(x, y - 10.0);
+                    }
+                    HitGrade::Great => self.spawn_delivery_particles(x, y),
+                    HitGrade::Good => self.spawn_particles(x, y, 8, &[0x44ff44ff, 0xffffffff]),
+                }
+                self.grade_popups.push(GradePopup { grade, x, y: y - 20.0, life: 45 });
+                if requested {
+                    self.spawn_star_particles(x, y - 30.0);
+                }
+            }
+            Event::GiftBroken { x, y } => {
+                self.screen_shake = self.screen_shake.max(3);
+                self.spawn_particles(x, y, 10, &[0xaaddffff, 0xffffffff, 0xff66aaff]);
+            }
+            Event::GiftRefused { x, y } => {
+                self.spawn_particles(x, y - 12.0, 8, &[0x333333ff, 0x555555ff, 0x777777ff]);
+            }
+            Event::GiftBounced { x, y } => {
+                self.spawn_particles(x, y, 4, &[COLOR_SNOW, 0xffffffff]);
+            }
+            Event::GiftLanded { x, y } => {
+                if self.sim.rivers.iter().any(|r| (r.x..r.x + r.width).contains(&x)) {
+                    self.spawn_particles(x, y, 10, &[0x4488ddff, 0x88bbeeff, 0xffffffff]);
+                } else {
+                    self.spawn_snow_puff(x, y);
+                }
+            }
+            Event::NaughtyRewarded { x, y } => {
+                self.screen_flash = 6;
+                self.flash_color = 0xff000066;
+                self.spawn_particles(x, y - 12.0, 10, &[0xff2222ff, 0x2a1a1aff]);
+            }
+            Event::LevelUp(_) => {
+                self.screen_flash = 15;
+                self.flash_color = COLOR_GOLD;
+                
+                // Spawn celebration particles
+                self.spawn_particles(SCREEN_W / 2.0, SCREEN_H / 2.0, 25, &[COLOR_GOLD, 0xffffffff, 0xff4444ff]);
+            }
+            Event::KrampusWarning => {
+                self.screen_shake = 10;
+            }
+            Event::KrampusArrived => {
+                self.screen_flash = 15;
+                self.flash_color = 0xff0000ff;
+                self.screen_shake = 15;
+            }
+            Event::SnowballThrown => {}
+            Event::KrampusHurt { x, y } => {
+                self.krampus_hit_flash = 8;
+                self.screen_shake = self.screen_shake.max(3);
+                self.spawn_particles(x, y, 8, &[COLOR_SNOW, 0xffffffff]);
+            }
+            Event::KrampusStage(_) => {
+                self.screen_flash = 12;
+                self.flash_color = 0xff0000ff;
+                self.screen_shake = 12;
+            }
+            Event::KrampusDefeated { x, y, .. } => {
+                self.run_krampus_survived += 1;
+                self.screen_flash = 20;
+                self.flash_color = COLOR_GOLD;
+                self.screen_shake = 10;
+                self.spawn_particles(x, y, 30, &[COLOR_GOLD, COLOR_CANDY, COLOR_SNOW]);
+            }
+            Event::EnemyHurt { x, y } => {
+                self.spawn_particles(x, y, 6, &[COLOR_SNOW, 0xffffffff]);
+            }
+            Event::EnemyDefeated { x, y, kind, .. } => {
+                self.screen_shake = self.screen_shake.max(4);
+                let colors: &[u32] = match kind {
+                    EnemyKind::SnowGolem => &[COLOR_SNOW, 0xffffffff, 0xaaccffff],
+                    EnemyKind::CoalThrower => &[0x333333ff, 0xff6600ff, COLOR_GOLD],
+                    _ => &[0xff2222ff, 0x2a1a1aff, COLOR_GOLD],
+                };
+                self.spawn_particles(x, y, 14, colors);
+            }
+            Event::KrampusSurvived { .. } => {
+                self.run_krampus_survived += 1;
+                self.screen_flash = 10;
+                self.flash_color = 0x00ff00ff;
+                self.screen_shake = 5;
+            }
+            Event::PowerUpCollected { x, y, .. } => {
+                self.spawn_star_particles(x, y);
+            }
+            Event::ShieldBlocked { x, y } => {
+                self.screen_flash = 8;
+                self.flash_color = 0x66ccffff;
+                self.screen_shake = 4;
+                self.spawn_particles(x, y, 12, &[0x66ccffff, 0xffffffff]);
             }
+            Event::PlayerHit => {
+                self.screen_flash = 15;
+                self.flash_color = 0xff0000ff;
+                self.screen_shake = 12;
+            }
+            Event::AttackSent { from, naughty } => {
+                self.screen_flash = 6;
+                self.flash_color = PLAYER_COLORS[from as usize % PLAYER_COLORS.len()];
+                self.attack_notice = Some((from, naughty, 90));
+            }
+            Event::Sfx(sfx) => self.sound.play_sfx(sfx),
         }
     }
     
-    fn save_high_score(&self) {
-        let bytes = self.high_score.to_le_bytes();
-        let _ = local::save(&bytes);
-    }
-    
     // ========================================================================
-    // PARTICLE SYSTEM
+    // STATE HOOKS
     // ========================================================================
     
-    fn spawn_particles(&mut self, x: f32, y: f32, count: u32, colors: &[u32]) {
-        // Pre-generate random values to avoid borrow conflicts
-        let mut particle_data: Vec<(f32, f32, u32, u32)> = Vec::with_capacity(count as usize);
-        for _ in 0..count {
-            let angle = self.random_range(0.0, 6.28);
-            let speed = self.random_range(1.0, 4.0);
-            let life = self.random() % 30 + 20;
-            let size = self.random() % 3 + 2;
-            particle_data.push((angle, speed, life, size));
-        }
-        
-        for (i, (angle, speed, life, size)) in particle_data.into_iter().enumerate() {
-            let color_idx = i % colors.len();
-            self.particles.push(Particle {
-                x,
-                y,
-                vel_x: angle.cos() * speed,
-                vel_y: angle.sin() * speed - 2.0,
-                life,
-                color: colors[color_idx],
-                size,
-            });
+    /// Leaving a state: pausing holds the music where it is
+    fn on_exit(&mut self, _from: GameMode, to: GameMode) {
+        if to == GameMode::Paused {
+            self.sound.pause_music();
         }
     }
     
-    fn spawn_delivery_particles(&mut self, x: f32, y: f32) {
-        // Confetti colors for successful delivery
-        let colors = [COLOR_GOLD, 0xff4444ff, 0x44ff44ff, 0x4444ffff, 0xff44ffff];
-        self.spawn_particles(x, y, 15, &colors);
-    }
-    
-    fn spawn_star_particles(&mut self, x: f32, y: f32) {
-        // Golden star particles
-        let colors = [COLOR_GOLD, COLOR_STAR, 0xffffaaff];
-        self.spawn_particles(x, y, 10, &colors);
+    /// Entering a state: crossfade to its music and start any transition effects
+    fn on_enter(&mut self, to: GameMode, from: GameMode) {
+        if from == GameMode::Paused {
+            self.sound.resume_music();
+        } else if to != GameMode::Paused {
+            self.sound.play_music(to.music());
+        }
+        
+        match to {
+            GameMode::Delivering if !from.in_run() => {
+                // Screen transition (fade in) at the start of a run
+                self.start_fade_in();
+            }
+            GameMode::GameOver => {
+                self.screen_shake = 25;
+                if self.playback.is_some() {
+                    self.finish_playback();
+                } else {
+                    self.finish_run();
+                }
+            }
+            _ => {}
+        }
     }
     
-    fn update_particles(&mut self) {
-        for particle in self.particles.iter_mut() {
-            particle.x += particle.vel_x;
-            particle.y += particle.vel_y;
-            particle.vel_y += 0.15;  // Gravity
-            if particle.life > 0 {
-                particle.life -= 1;
+    /// Record the score and stats and keep the replay once a run ends
+    fn finish_run(&mut self) {
+        let entry = ScoreEntry {
+            score: self.sim.score,
+            deliveries: self.sim.deliveries,
+            max_combo: self.sim.max_combo,
+            level: self.sim.level,
+            initials: self.last_initials,
+        };
+        if let RunMode::Campaign(night) = self.sim.run_mode {
+            // Nights keep their own bests; clearing one moves on to the next
+            self.save.record_stats(&entry, self.run_krampus_survived);
+            let stars = self.sim.night_stars();
+            let new_best = self.save.record_night(night, self.sim.score, stars);
+            self.night_result = Some(NightResult { night, stars, new_best });
+            if stars > 0 && self.sim.campaign.get(night + 1).is_some() {
+                self.sim.run_mode = RunMode::Campaign(night + 1);
             }
+        } else if self.sim.run_mode == RunMode::Versus {
+            // Two scores in one run don't belong on the leaderboard
+            self.save.record_stats(&entry, self.run_krampus_survived);
+        } else {
+            self.new_high_score = self.sim.score > self.save.high_score;
+            let rank = self.save.record_run(entry, self.run_krampus_survived);
+            self.initials_entry = rank.map(|rank| InitialsEntry::new(rank, self.last_initials));
         }
-        self.particles.retain(|p| p.life > 0);
+        if let RunMode::Daily(day) = self.sim.run_mode {
+            let accuracy = self.sim.accuracy();
+            self.new_daily_best = self.save.record_daily(day, self.sim.score, accuracy);
+            let share = daily::share_text(day, self.sim.score, accuracy);
+            log!("{}", share);
+            events::emit("daily", &json!({ "day": day, "score": self.sim.score, "share": share }).to_string());
+            self.daily_share = Some(share);
+        }
+        self.save.first_play = false;
+        self.write_save();
+        
+        let mut replay = std::mem::replace(&mut self.recording, Replay::new(0, RunMode::Classic, 1, false));
+        replay.finish(&self.sim);
+        Self::export_replay(&replay);
+        // Versus is two rivals rather than one run to beat
+        self.new_ghost = self.sim.run_mode != RunMode::Versus && self.save.record_ghost(&replay);
+        if self.new_ghost {
+            self.write_save();
+            Self::export_ghost(&replay);
+        }
+        self.last_replay = Some(replay);
+        self.replay_verified = None;
     }
     
     // ========================================================================
-    // POWER-UP SYSTEM
+    // MENUS AND LEADERBOARD
     // ========================================================================
     
-    fn spawn_powerup(&mut self) {
-        let kind = if self.random() % 3 == 0 { POWERUP_INVINCIBLE } else { POWERUP_HEALTH };
-        let y = self.random_range(40.0, SCREEN_H * 0.6);
-        let bob_offset = self.random_range(0.0, 6.28);
-        self.powerups.push(PowerUp {
-            x: SCREEN_W + 20.0,
-            y,
-            kind,
-            active: true,
-            bob_offset,
-        });
-    }
-    
-    fn update_powerups(&mut self) {
-        // Spawn timer
-        if self.powerup_spawn_timer > 0 {
-            self.powerup_spawn_timer -= 1;
-        } else {
-            self.spawn_powerup();
-            self.powerup_spawn_timer = 900 + self.random() % 600;
+    /// Handle buttons for screens outside the simulation. Returns true if one of
+    /// them owns the controls this frame, so gameplay input is held back.
+    fn handle_menu_input(&mut self) -> bool {
+        let gp = gamepad::get(0);
+        let kb = keyboard::get();
+        let confirm = gp.a.just_pressed() || gp.start.just_pressed() || kb.enter().just_pressed();
+        let back = gp.b.just_pressed() || kb.escape().just_pressed() || kb.backspace().just_pressed();
+        
+        if let Some(entry) = &mut self.initials_entry {
+            if gp.up.just_pressed() { entry.cycle(true); }
+            if gp.down.just_pressed() { entry.cycle(false); }
+            if gp.left.just_pressed() || back { entry.cursor = entry.cursor.saturating_sub(1); }
+            if gp.right.just_pressed() { entry.cursor = (entry.cursor + 1).min(2); }
+            if confirm {
+                if entry.cursor < 2 {
+                    entry.cursor += 1;
+                } else {
+                    let entry = *entry;
+                    self.confirm_initials(entry);
+                }
+            }
+            return true;
         }
         
-        // Collect collision data first to avoid borrow conflicts
-        let mut collected: Vec<(f32, f32, u8)> = Vec::new();
-        let player_y = self.player_y;
-        let scroll_speed = self.scroll_speed;
-        
-        for powerup in self.powerups.iter_mut() {
-            powerup.x -= scroll_speed;
-            powerup.bob_offset += 0.1;
-            
-            let bob_y = powerup.y + (powerup.bob_offset.sin() * 5.0);
-            let dx = (PLAYER_X - powerup.x).abs();
-            let dy = (player_y - bob_y).abs();
-            
-            if dx < 25.0 && dy < 20.0 && powerup.active {
-                powerup.active = false;
-                collected.push((powerup.x, bob_y, powerup.kind));
+        if let Some(import) = &mut self.ghost_import {
+            import.type_chars(&kb.chars());
+            if kb.backspace().just_pressed() { import.backspace(); }
+            // Keyboard only: gamepad buttons may sit on letter keys
+            if kb.enter().just_pressed() {
+                if let Some(replay) = import.finish() {
+                    self.import_ghost(replay);
+                }
+            } else if kb.escape().just_pressed() {
+                self.ghost_import = None;
             }
+            return true;
         }
         
-        // Now process collected power-ups
-        for (x, y, kind) in collected {
-            self.spawn_star_particles(x, y);
-            match kind {
-                POWERUP_HEALTH => {
-                    self.health = (self.health + 1).min(5);
-                    Self::play_sfx("delivery");
+        match self.menu {
+            Menu::Leaderboard => {
+                if confirm || back || gp.select.just_pressed() || kb.key_l().just_pressed() {
+                    self.menu = Menu::None;
                 }
-                POWERUP_INVINCIBLE => {
-                    self.star_power_timer = 300;
-                    Self::play_sfx("survive");
+                true
+            }
+            Menu::Options => {
+                if gp.up.just_pressed() {
+                    self.options_cursor = (self.options_cursor + OPTIONS.len() - 1) % OPTIONS.len();
+                }
+                if gp.down.just_pressed() {
+                    self.options_cursor = (self.options_cursor + 1) % OPTIONS.len();
                 }
-                _ => {}
+                if gp.left.just_pressed() { self.change_option(-1); }
+                if gp.right.just_pressed() { self.change_option(1); }
+                if confirm { self.change_option(0); }
+                if back || kb.key_o().just_pressed() {
+                    self.menu = Menu::None;
+                }
+                true
             }
-        }
-        
-        // Remove off-screen or collected power-ups
-        self.powerups.retain(|p| p.x > -30.0 && p.active);
-        
-        // Update star power timer
-        if self.star_power_timer > 0 {
-            self.star_power_timer -= 1;
+            Menu::None => match self.sim.mode {
+                GameMode::Title | GameMode::GameOver if gp.select.just_pressed() || kb.key_l().just_pressed() => {
+                    self.open_menu(Menu::Leaderboard);
+                    true
+                }
+                GameMode::Title | GameMode::Paused if gp.x.just_pressed() || kb.key_o().just_pressed() => {
+                    self.options_cursor = 0;
+                    self.tutorial_reset = false;
+                    self.open_menu(Menu::Options);
+                    true
+                }
+                GameMode::Title if gp.left.just_pressed() || gp.right.just_pressed() => {
+                    self.sim.run_mode = match self.sim.run_mode.cycle(gp.right.just_pressed()) {
+                        RunMode::Campaign(_) => RunMode::Campaign(self.nights_unlocked()),
+                        RunMode::Daily(_) => RunMode::Daily(self.today()),
+                        mode => mode,
+                    };
+                    // An imported ghost brings its own mode; the personal best follows this one
+                    if self.ghost_source == GhostSource::Imported {
+                        self.ghost_source = GhostSource::Off;
+                    }
+                    self.apply_players();
+                    true
+                }
+                GameMode::Title if gp.up.just_pressed() || gp.down.just_pressed() => {
+                    // Pick among the unlocked campaign nights
+                    if let RunMode::Campaign(night) = self.sim.run_mode {
+                        let last = self.nights_unlocked();
+                        let night = if gp.up.just_pressed() { night % last + 1 } else { (night + last - 2) % last + 1 };
+                        self.sim.run_mode = RunMode::Campaign(night);
+                        if self.ghost_source == GhostSource::Imported {
+                            self.ghost_source = GhostSource::Off;
+                        }
+                    }
+                    true
+                }
+                GameMode::Title if gp.y.just_pressed() || kb.key_g().just_pressed() => {
+                    self.cycle_ghost();
+                    true
+                }
+                GameMode::Title if kb.key_i().just_pressed() => {
+                    self.ghost_import = Some(GhostImport::default());
+                    self.pending_input = [Input::default(); MAX_PLAYERS];
+                    true
+                }
+                GameMode::GameOver if self.last_replay.is_some() && (kb.key_r().just_pressed() || gp.y.just_pressed()) => {
+                    self.watch_last_replay();
+                    false
+                }
+                _ => false,
+            },
         }
     }
     
-    // ========================================================================
-    // COMBO SYSTEM
-    // ========================================================================
+    /// Today's daily challenge day from the wall clock, or the last one played if
+    /// the clock isn't set
+    fn today(&self) -> u32 {
+        daily::today(time::now(), self.save.daily.day)
+    }
     
-    fn add_combo(&mut self) {
-        self.combo_count += 1;
-        self.combo_timer = 180;  // 3 seconds to maintain combo
-        if self.combo_count > self.max_combo {
-            self.max_combo = self.combo_count;
-        }
-        
-        // Bonus points for combos
-        let bonus = match self.combo_count {
-            2 => 50,
-            3 => 100,
-            4 => 200,
-            5..=9 => 300,
-            _ => 500,
-        };
-        self.score += bonus;
+    /// Campaign nights open to play, counting from December 1st
+    fn nights_unlocked(&self) -> u8 {
+        self.save.nights_unlocked(self.sim.campaign.nights.len() as u8)
     }
     
-    fn update_combo(&mut self) {
-        if self.combo_timer > 0 {
-            self.combo_timer -= 1;
-        } else if self.combo_count > 0 {
-            self.combo_count = 0;
+    /// Adjust the highlighted option: -1/1 for left/right, 0 for confirm
+    fn change_option(&mut self, dir: i32) {
+        let settings = &mut self.save.settings;
+        let step = |v: f32, by: f32| ((v + by * dir.signum() as f32) * 10.0).round().clamp(0.0, 10.0) / 10.0;
+        match OPTIONS[self.options_cursor] {
+            "Music" => {
+                settings.music_volume = step(settings.music_volume, 0.1);
+                self.apply_volumes();
+            }
+            "Sound FX" => {
+                settings.sfx_volume = step(settings.sfx_volume, 0.1);
+                self.apply_volumes();
+                self.sound.play_sfx(Sfx::Drop);  // Preview
+            }
+            "Screen shake" => settings.screen_shake = !settings.screen_shake,
+            "Aim guide" => settings.aim_guide = !settings.aim_guide,
+            "Players" => {
+                settings.players = if settings.players == 1 { 2 } else { 1 };
+                self.apply_players();
+            }
+            "Lives" => {
+                settings.shared_lives = !settings.shared_lives;
+                self.apply_players();
+            }
+            "Flash" => {
+                settings.flash_intensity = match dir {
+                    // Confirm steps down and wraps from Off back to full
+                    0 if settings.flash_intensity <= 0.0 => 1.0,
+                    0 => (settings.flash_intensity - 0.25).max(0.0),
+                    _ => (settings.flash_intensity + 0.25 * dir as f32).clamp(0.0, 1.0),
+                };
+            }
+            "Reset tutorial" if dir == 0 => {
+                self.save.first_play = true;
+                self.tutorial_reset = true;
+            }
+            "Back" if dir == 0 => {
+                self.menu = Menu::None;
+                return;
+            }
+            _ => return,
         }
+        self.write_save();
+    }
+    
+    fn open_menu(&mut self, menu: Menu) {
+        self.menu = menu;
+        self.pending_input = [Input::default(); MAX_PLAYERS];
     }
     
-    fn break_combo(&mut self) {
-        self.combo_count = 0;
-        self.combo_timer = 0;
+    /// Store the typed initials on the run's leaderboard row and show the table
+    fn confirm_initials(&mut self, entry: InitialsEntry) {
+        if let Some(row) = self.save.leaderboard.get_mut(entry.rank) {
+            row.initials = entry.letters;
+        }
+        self.last_initials = entry.letters;
+        self.initials_entry = None;
+        self.highlight_rank = Some(entry.rank);
+        self.write_save();
+        self.open_menu(Menu::Leaderboard);
+        self.sound.play_sfx(Sfx::Delivery);
     }
     
     // ========================================================================
-    // PROGRESSIVE DIFFICULTY
+    // REPLAYS
     // ========================================================================
     
-    fn update_difficulty(&mut self) {
-        // Increase scroll speed based on level
-        self.scroll_speed = self.base_scroll_speed + (self.level as f32 - 1.0) * 0.2;
-        
-        // Cap at reasonable speed
-        self.scroll_speed = self.scroll_speed.min(3.5);
+    /// Hand a finished run to the host page so it can be saved as a replay file
+    fn export_replay(replay: &Replay) {
+        let data = json!({
+            "seed": replay.seed,
+            "score": replay.summary.score,
+            "replay": replay.to_hex(),
+        });
+        events::emit("replay", &data.to_string());
     }
     
-    fn level_up(&mut self) {
-        self.level += 1;
-        self.update_difficulty();
-        
-        // Level up effects
-        self.screen_flash = 15;
-        self.flash_color = COLOR_GOLD;
-        Self::play_sfx("survive");  // Jingle for level up
-        
-        // Spawn celebration particles
-        self.spawn_particles(SCREEN_W / 2.0, SCREEN_H / 2.0, 25, &[COLOR_GOLD, 0xffffffff, 0xff4444ff]);
+    /// Hand a new personal best ghost to the host page so it can be shared as a file
+    fn export_ghost(replay: &Replay) {
+        let hex = replay.to_hex();
+        log!("New ghost: {}", hex);
+        events::emit("ghost", &json!({ "score": replay.summary.score, "ghost": hex }).to_string());
     }
     
-    // ========================================================================
-    // SCREEN TRANSITIONS
-    // ========================================================================
+    /// The replay behind the chosen ghost, if there is one to race
+    fn ghost_replay(&self) -> Option<Replay> {
+        match self.ghost_source {
+            GhostSource::Off => None,
+            GhostSource::Best => self.save.best_ghost(self.sim.run_mode, self.sim.player_count),
+            GhostSource::Imported => self.imported_ghost.clone(),
+        }
+    }
     
-    fn update_fade(&mut self) {
-        match self.fade_direction {
-            1 => {  // Fading in (alpha decreasing)
-                if self.fade_alpha > 8 {
-                    self.fade_alpha -= 8;
-                } else {
-                    self.fade_alpha = 0;
-                    self.fade_direction = 0;
-                }
-            }
-            -1 => {  // Fading out (alpha increasing)
-                if self.fade_alpha < 247 {
-                    self.fade_alpha += 8;
-                } else {
-                    self.fade_alpha = 255;
-                    self.fade_direction = 0;
+    /// Step to the next ghost there is one for. The personal best is the one
+    /// for the chosen mode and player count; an imported ghost brings its own mode.
+    fn cycle_ghost(&mut self) {
+        self.apply_players();
+        loop {
+            self.ghost_source = self.ghost_source.next();
+            if self.ghost_source == GhostSource::Off { break; }
+            if let Some(replay) = self.ghost_replay() {
+                if self.ghost_source == GhostSource::Imported {
+                    self.sim.run_mode = replay.mode;
                 }
+                break;
             }
-            _ => {}
         }
     }
     
-    fn start_fade_out(&mut self) {
-        self.fade_direction = -1;
+    /// Race an imported run next, in the mode it was played in
+    fn import_ghost(&mut self, replay: Replay) {
+        log!("Imported ghost: {} ({})", replay.mode.name(), replay.summary.score);
+        self.sim.run_mode = replay.mode;
+        self.imported_ghost = Some(replay);
+        self.ghost_source = GhostSource::Imported;
+        self.ghost_import = None;
+        self.sound.play_sfx(Sfx::Delivery);
     }
     
-    fn start_fade_in(&mut self) {
-        self.fade_alpha = 255;
-        self.fade_direction = 1;
+    /// Re-run the last finished game from its recorded input
+    fn watch_last_replay(&mut self) {
+        self.ghost = None;
+        if let Some(replay) = &self.last_replay {
+            self.sim = replay.start();
+            self.sound.play_music(self.sim.mode.music());
+            self.playback = Some(replay.clone());
+            self.playback_tick = 0;
+            self.replay_verified = None;
+        }
     }
     
-    // ========================================================================
-    // PAUSE SYSTEM
-    // ========================================================================
-    
-    fn toggle_pause(&mut self) {
-        if self.mode == MODE_PAUSED {
-            self.mode = self.previous_mode;
-            self.play_mode_music();
-        } else if self.mode == MODE_DELIVERING || self.mode == MODE_KRAMPUS {
-            self.previous_mode = self.mode;
-            self.mode = MODE_PAUSED;
-            audio::stop("game");
-            audio::stop("krampus");
+    /// Compare a watched replay's outcome against what was recorded
+    fn finish_playback(&mut self) {
+        if let Some(replay) = self.playback.take() {
+            let used_all_input = self.playback_tick as usize == replay.frames.len();
+            self.replay_verified = Some(used_all_input && RunSummary::of(&self.sim) == replay.summary);
         }
     }
     
     // ========================================================================
-    // PLAYER MOVEMENT
+    // SAVE DATA
     // ========================================================================
     
-    fn move_player(&mut self) {
-        let gp = gamepad::get(0);
-        
-        // Vertical movement only
-        if gp.up.pressed() {
-            self.player_vel_y = -PLAYER_SPEED;
-            self.sleigh_tilt = -8.0; // Tilt up
-        } else if gp.down.pressed() {
-            self.player_vel_y = PLAYER_SPEED;
-            self.sleigh_tilt = 8.0; // Tilt down
-        } else {
-            self.player_vel_y *= 0.85; // Deceleration
-            self.sleigh_tilt *= 0.8;   // Return to level
-        }
-        
-        self.player_y += self.player_vel_y;
-        self.player_y = self.player_y.clamp(20.0, SCREEN_H - 30.0);
-    }
-    
-    // CHIMNEY SYSTEM
-        
-    fn spawn_chimney(&mut self) {
-        // Spawn chimneys on the ground (78% of screen height)
-        let ground_y = SCREEN_H * 0.78;
-        let y = self.random_range(ground_y - 30.0, ground_y - 10.0);
-        // Random house style (0-2)
-        let style = (self.random_range(0.0, 3.0) as u8).min(2);
-        self.chimneys.push(Chimney {
-            x: SCREEN_W + 40.0,
-            y,
-            delivered: false,
-            style,
-        });
-        
-        // Next chimney spawn distance (varies, more space for larger screen)
-        self.next_chimney_spawn = self.random_range(120.0, 200.0);
-    }
-    
-    fn update_chimneys(&mut self) {
-        // Spawn new chimneys
-        if self.chimneys.is_empty() || 
-           self.chimneys.last().map(|c| c.x < SCREEN_W - self.next_chimney_spawn).unwrap_or(true) {
-            self.spawn_chimney();
-        }
-        
-        // Track missed chimneys
-        let mut missed_count = 0;
-        
-        // Update positions and remove off-screen
-        self.chimneys.retain(|c| {
-            if c.x < -40.0 {
-                if !c.delivered {
-                    missed_count += 1;
-                }
-                false
-            } else {
-                true
+    fn load_save(&mut self) {
+        let data = local::load().unwrap_or_default();
+        self.save = match SaveData::decode(&data) {
+            Ok(save) => save,
+            Err(err) => {
+                log!("Ignoring save data: {}", err);
+                SaveData::default()
             }
-        });
-        
-        // Increase naughty meter for missed deliveries
-        self.naughty_meter = (self.naughty_meter + missed_count * 20).min(100);
-        
-        // Move chimneys
-        for chimney in &mut self.chimneys {
-            chimney.x -= self.scroll_speed;
-        }
+        };
+        self.apply_volumes();
+        self.apply_players();
     }
     
+    fn write_save(&self) {
+        let _ = local::save(&self.save.encode());
+    }
     
-    // GIFT DROPPING
-    
+    // ========================================================================
+    // PARTICLE SYSTEM
+    // ========================================================================
     
-    fn drop_gift(&mut self) {
-        let gp = gamepad::get(0);
-        let kb = keyboard::get();
-        
-        // Drop with Enter key, Space, or A/B button
-        let should_drop = kb.enter().just_pressed() 
-            || kb.space().just_pressed()
-            || gp.a.just_pressed() 
-            || gp.b.just_pressed();
-        
-        if should_drop {
-            // Find the nearest chimney ahead (increased range for easier aiming)
-            let mut best_chimney: Option<usize> = None;
-            let mut best_dist = f32::MAX;
-            
-            for (i, chimney) in self.chimneys.iter().enumerate() {
-                // Larger detection window: 150 pixels ahead
-                if !chimney.delivered && chimney.x > PLAYER_X - 20.0 && chimney.x < PLAYER_X + 150.0 {
-                    let dist = (chimney.x - PLAYER_X).abs();
-                    if dist < best_dist {
-                        best_dist = dist;
-                        best_chimney = Some(i);
-                    }
-                }
-            }
-            
-            // Drop a gift
-            self.gifts.push(FallingGift {
-                x: PLAYER_X + 8.0,
-                y: self.player_y + 12.0,
-                vel_y: 1.0,
-                target_chimney: best_chimney.unwrap_or(usize::MAX),
-                active: true,
+    fn spawn_particles(&mut self, x: f32, y: f32, count: u32, colors: &[u32]) {
+        for i in 0..count as usize {
+            let angle = self.fx_rng.range(0.0, std::f32::consts::TAU);
+            let speed = self.fx_rng.range(1.0, 4.0);
+            let life = self.fx_rng.next_u32() % 30 + 20;
+            let size = self.fx_rng.next_u32() % 3 + 2;
+            self.particles.push(Particle {
+                x,
+                y,
+                vel_x: angle.cos() * speed,
+                vel_y: angle.sin() * speed - 2.0,
+                life,
+                color: colors[i % colors.len()],
+                size,
             });
-            
-            // Visual feedback that gift was dropped
-            self.screen_flash = 2;
-            self.flash_color = 0xffffff44;
-            
-            // Drop sound
-            Self::play_sfx("drop");
         }
     }
     
-    fn update_gifts(&mut self) {
-        let scroll_speed = self.scroll_speed;
-        let mut score_gained = 0u32;
-        let mut deliveries_made = 0u32;
-        let mut delivery_positions: Vec<(f32, f32)> = Vec::new();  // For particles
-        
-        // Get chimney positions for collision
-        let chimney_data: Vec<(f32, f32, bool)> = self.chimneys
-            .iter()
-            .map(|c| (c.x, c.y, c.delivered))
-            .collect();
-        
-        for gift in &mut self.gifts {
-            if !gift.active { continue; }
-            
-            // Gift falls with arc
-            gift.x -= scroll_speed * 0.3;
-            gift.y += gift.vel_y;
-            gift.vel_y += 0.15;
-            
-            // Check collision with chimneys
-            for (i, &(cx, cy, delivered)) in chimney_data.iter().enumerate() {
-                if !delivered {
-                    let dx = gift.x - cx;
-                    let dy = gift.y - cy;
-                    
-                    // GENEROUS hitbox
-                    if dx.abs() < 25.0 && dy > -10.0 && dy < 30.0 {
-                        gift.active = false;
-                        score_gained += 100 + self.level * 10;
-                        deliveries_made += 1;
-                        delivery_positions.push((cx, cy));
-                        
-                        // Mark chimney as delivered
-                        if i < self.chimneys.len() {
-                            self.chimneys[i].delivered = true;
-                        }
-                        break;
-                    }
-                }
-            }
-            
-            // Remove if off screen
-            if gift.y > SCREEN_H + 20.0 || gift.x < -20.0 {
-                gift.active = false;
-            }
-        }
-        
-        // Check for any gifts that fell off screen (missed)
-        let had_missed = self.gifts.iter().any(|g| !g.active && g.y > SCREEN_H);
-        
-        // Apply score and deliveries
-        self.score += score_gained;
-        self.deliveries += deliveries_made;
-        
-        // Break combo if missed
-        if had_missed {
-            self.break_combo();
-        }
-        
-        // Effects on delivery
-        if deliveries_made > 0 {
-            self.screen_flash = 4;
-            self.flash_color = 0x00ff00ff;
-            self.naughty_meter = self.naughty_meter.saturating_sub(10);
-            Self::play_sfx("delivery");
-            
-            // Add combo for each delivery
-            for _ in 0..deliveries_made {
-                self.add_combo();
-            }
-            
-            // Spawn particles at delivery locations
-            for (x, y) in delivery_positions {
-                self.spawn_delivery_particles(x, y);
-            }
-            
-            // Level up every 5 deliveries
-            if self.deliveries % 5 == 0 && self.deliveries > 0 {
-                self.level_up();
-            }
-        }
-        
-        // Clean up inactive gifts
-        self.gifts.retain(|g| g.active);
+    fn spawn_delivery_particles(&mut self, x: f32, y: f32) {
+        // Confetti colors for successful delivery
+        let colors = [COLOR_GOLD, COLOR_CANDY, 0x44ff44ff, 0x4444ffff, 0xff44ffff];
+        self.spawn_particles(x, y, 15, &colors);
     }
     
-    
-    // KRAMPUS SYSTEM
-    
-    
-    fn check_krampus_trigger(&mut self) {
-        if self.krampus_active || self.krampus_warning > 0 { return; }
-        
-        // Trigger conditions: timer or naughty meter
-        self.krampus_attack_timer = self.krampus_attack_timer.saturating_sub(1);
-        
-        if self.krampus_attack_timer == 0 || self.naughty_meter >= 80 {
-            // Start warning countdown
-            self.krampus_warning = 120; // 2 seconds warning
-            self.screen_shake = 10;
-            
-            // Warning sound
-            Self::play_sfx("warning");
+    /// Low spray of snow where a missed gift came down
+    fn spawn_snow_puff(&mut self, x: f32, y: f32) {
+        for i in 0..12 {
+            let vel_x = self.fx_rng.range(-1.5, 1.5);
+            let vel_y = self.fx_rng.range(-2.5, -0.8);
+            let life = self.fx_rng.next_u32() % 15 + 15;
+            self.particles.push(Particle {
+                x: x + vel_x * 3.0,
+                y,
+                vel_x,
+                vel_y,
+                life,
+                color: if i % 3 == 0 { 0xc0d0e0ff } else { COLOR_SNOW },
+                size: 3 + i % 3,
+            });
         }
     }
     
-    fn update_krampus_warning(&mut self) {
-        if self.krampus_warning > 0 {
-            self.krampus_warning -= 1;
-            
-            if self.krampus_warning == 0 {
-                // Krampus attack begins!
-                self.mode = MODE_KRAMPUS;
-                self.krampus_active = true;
-                self.krampus_x = SCREEN_W + 30.0;
-                self.krampus_y = self.random_range(40.0, SCREEN_H - 40.0);
-                self.krampus_duration = 360; // 6 seconds
-                self.screen_flash = 15;
-                self.flash_color = 0xff0000ff;
-                self.screen_shake = 15;
-                
-                // Krampus attack music and sound
-                self.play_mode_music();
-                Self::play_sfx("krampus");
-            }
-        }
+    fn spawn_star_particles(&mut self, x: f32, y: f32) {
+        // Golden star particles
+        let colors = [COLOR_GOLD, COLOR_STAR, 0xffffaaff];
+        self.spawn_particles(x, y, 10, &colors);
     }
     
-    fn update_krampus(&mut self) {
-        if !self.krampus_active { return; }
-        
-        // Krampus flies in from right
-        if self.krampus_x > SCREEN_W - 60.0 {
-            self.krampus_x -= 3.0; // Faster entry
-        }
-        
-        // Krampus tracks player Y more aggressively
-        let dy = self.player_y - self.krampus_y;
-        self.krampus_y += dy * 0.035;
-        
-        // Add bobbing motion for menace
-        self.krampus_y += (self.frame as f32 / 10.0).sin() * 0.5;
-        
-        // Fire projectiles - rate increases with level
-        let fire_rate = (50 - (self.level * 5).min(25)).max(20);
-        if self.frame % fire_rate == 0 {
-            self.fire_projectile_pattern();
+    fn update_particles(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.x += particle.vel_x;
+            particle.y += particle.vel_y;
+            particle.vel_y += 0.15;  // Gravity
+            if particle.life > 0 {
+                particle.life -= 1;
+            }
         }
+        self.particles.retain(|p| p.life > 0);
         
-        // Duration countdown
-        self.krampus_duration = self.krampus_duration.saturating_sub(1);
-        
-        if self.krampus_duration == 0 {
-            // Krampus retreats
-            self.krampus_active = false;
-            self.mode = MODE_DELIVERING;
-            self.krampus_attack_timer = (600 - (self.level * 40).min(400)).max(180);
-            self.naughty_meter = 0;
-            
-            // Survival bonus
-            self.score += 200 + self.level * 50;
-            self.screen_flash = 10;
-            self.flash_color = 0x00ff00ff;
-            self.screen_shake = 5;
-            
-            // Back to normal music + victory sound
-            self.play_mode_music();
-            Self::play_sfx("survive");
+        for popup in self.grade_popups.iter_mut() {
+            popup.y -= 0.5;
+            popup.life = popup.life.saturating_sub(1);
         }
+        self.grade_popups.retain(|p| p.life > 0);
     }
     
-    /// Fire projectiles with varying patterns based on level
-    fn fire_projectile_pattern(&mut self) {
-        let pattern = (self.frame / 60 + self.level as u32) % 4;
-        let base_speed = 2.5 + self.level as f32 * 0.3;
-        
-        let dx = PLAYER_X - self.krampus_x;
-        let dy = self.player_y - self.krampus_y;
-        let dist = (dx * dx + dy * dy).sqrt().max(1.0);
-        let dir_x = dx / dist;
-        let dir_y = dy / dist;
-        
-        match pattern {
-            0 => {
-                // Single aimed shot
-                self.spawn_projectile(dir_x * base_speed, dir_y * base_speed);
-            }
-            1 => {
-                // 3-way spread
-                self.spawn_projectile(dir_x * base_speed, dir_y * base_speed);
-                self.spawn_projectile(dir_x * base_speed - 0.8, dir_y * base_speed - 0.8);
-                self.spawn_projectile(dir_x * base_speed + 0.8, dir_y * base_speed + 0.8);
-            }
-            2 => {
-                // Horizontal wave
-                self.spawn_projectile(-base_speed, -1.5);
-                self.spawn_projectile(-base_speed, 0.0);
-                self.spawn_projectile(-base_speed, 1.5);
-            }
-            _ => {
-                // Diagonal cross
-                self.spawn_projectile(-base_speed, -base_speed * 0.5);
-                self.spawn_projectile(-base_speed, base_speed * 0.5);
-            }
-        }
-    }
-    
-    fn spawn_projectile(&mut self, vel_x: f32, vel_y: f32) {
-        self.projectiles.push(Projectile {
-            x: self.krampus_x - 15.0,
-            y: self.krampus_y,
-            vel_x,
-            vel_y,
-            active: true,
-        });
-    }
+    // ========================================================================
+    // SCREEN TRANSITIONS
+    // ========================================================================
     
-    fn update_projectiles(&mut self) {
-        let player_y = self.player_y;
-        let mut hit = false;
-        let is_invincible = self.invincible_timer > 0;
-        
-        for proj in &mut self.projectiles {
-            if !proj.active { continue; }
-            
-            proj.x += proj.vel_x;
-            proj.y += proj.vel_y;
-            
-            // Check collision with player (only if not invincible)
-            if !is_invincible {
-                let dx = proj.x - PLAYER_X;
-                let dy = proj.y - player_y;
-                let dist = (dx * dx + dy * dy).sqrt();
-                
-                if dist < 14.0 {
-                    proj.active = false;
-                    hit = true;
+    fn update_fade(&mut self) {
+        match self.fade_direction {
+            1 => {  // Fading in (alpha decreasing)
+                if self.fade_alpha > 8 {
+                    self.fade_alpha -= 8;
+                } else {
+                    self.fade_alpha = 0;
+                    self.fade_direction = 0;
                 }
             }
-            
-            // Remove if off screen
-            if proj.x < -20.0 || proj.x > SCREEN_W + 20.0 ||
-               proj.y < -20.0 || proj.y > SCREEN_H + 20.0 {
-                proj.active = false;
-            }
-        }
-        
-        if hit {
-            self.health = self.health.saturating_sub(1);
-            self.screen_flash = 15;
-            self.flash_color = 0xff0000ff;
-            self.screen_shake = 12;
-            self.invincible_timer = 90; // 1.5 seconds of invincibility
-            
-            // Hit sound
-            Self::play_sfx("hit");
-            
-            if self.health == 0 {
-                self.mode = MODE_GAMEOVER;
-                self.screen_shake = 25;
-                self.invincible_timer = 0;
-                
-                // Game over music and sound
-                self.play_mode_music();
-                Self::play_sfx("game-over");
+            -1 => {  // Fading out (alpha increasing)
+                if self.fade_alpha < 247 {
+                    self.fade_alpha += 8;
+                } else {
+                    self.fade_alpha = 255;
+                    self.fade_direction = 0;
+                }
             }
+            _ => {}
         }
-        
-        // Decrement invincibility
-        if self.invincible_timer > 0 {
-            self.invincible_timer -= 1;
-        }
-        
-        self.projectiles.retain(|p| p.active);
     }
     
-    
-    // SCROLLING & PARALLAX
-    
-    
-    fn update_scroll(&mut self) {
-        self.scroll_x += self.scroll_speed;
-        
-        // Progressive difficulty
-        if self.deliveries > 0 && self.deliveries % 10 == 0 && self.frame % 60 == 0 {
-            self.level += 1;
-            self.scroll_speed = (1.5 + self.level as f32 * 0.2).min(4.0);
-        }
+    fn start_fade_in(&mut self) {
+        self.fade_alpha = 255;
+        self.fade_direction = 1;
     }
     
+    // PARALLAX SNOW
+    
     fn update_snowflakes(&mut self) {
         let frame = self.frame;
-        let scroll = self.scroll_speed;
+        let scroll = self.sim.scroll_speed;
+        let wind = if self.sim.in_run() { self.sim.wind() * 40.0 } else { 0.0 };
         
         for (i, snow) in self.snowflakes.iter_mut().enumerate() {
             snow.y += snow.speed;
             snow.x -= scroll * 0.5; // Move with background
+            snow.x += wind;
             snow.x += (frame as f32 / 20.0 + i as f32).sin() * 0.3;
             
             if snow.y > SCREEN_H + 5.0 {
@@ -986,6 +938,7 @@ impl GameState {
                 snow.x = (frame.wrapping_add(i as u32 * 7919) % 256) as f32;
             }
             if snow.x < -5.0 { snow.x = SCREEN_W + 5.0; }
+            if snow.x > SCREEN_W + 5.0 { snow.x = -5.0; }
         }
     }
     
@@ -994,7 +947,7 @@ impl GameState {
     
     
     fn get_shake(&self) -> (i32, i32) {
-        if self.screen_shake > 0 {
+        if self.screen_shake > 0 && self.save.settings.screen_shake {
             let intensity = (self.screen_shake as f32 / 3.0).min(4.0);
             let sx = ((self.frame as f32 * 1.7).sin() * intensity) as i32;
             let sy = ((self.frame as f32 * 2.3).cos() * intensity) as i32;
@@ -1010,24 +963,23 @@ impl GameState {
     
     fn draw_background(&self, shake_x: i32, shake_y: i32) {
         // Level-based sky color
-        let sky_idx = ((self.level - 1) as usize).min(4);
-        let sky_color = SKY_COLORS[sky_idx];
+        let sky_color = self.sim.config().sky_color;
         clear(sky_color);
         
         // Aurora effect at higher levels (level 3+)
-        if self.level >= 3 {
+        if self.sim.level >= 3 {
             let aurora_offset = (self.frame as f32 / 30.0).sin() * 20.0;
             for i in 0..5u32 {
                 let ay = 30 + i as i32 * 8 + aurora_offset as i32;
                 let alpha = 0x22u32 - i * 0x04;
-                let color = if self.level >= 4 { 0x8800ff00 + alpha } else { 0x00ff8800 + alpha };
+                let color = if self.sim.level >= 4 { 0x8800ff00 + alpha } else { 0x00ff8800 + alpha };
                 rect!(x = 0, y = ay, w = SCREEN_W as u32, h = 6, color = color);
             }
         }
         
         // Stars (far layer) - enhanced with twinkle effect
         for i in 0..40u32 {
-            let star_x = ((i * 47 + 10) as f32 - (self.scroll_x * 0.1) % SCREEN_W) as i32;
+            let star_x = ((i * 47 + 10) as f32 - (self.sim.scroll_x * 0.1) % SCREEN_W) as i32;
             let star_y = (i * 7 % 90 + 5) as i32;
             let twinkle = if (self.frame + i * 17) % 60 < 30 { 0xffffffff } else { 0xffffff88 };
             let size = if i % 5 == 0 { 3 } else { 2 };
@@ -1046,17 +998,17 @@ impl GameState {
         circ!(x = moon_x, y = moon_y, d = 45, color = 0xffff8811);
         circ!(x = moon_x, y = moon_y, d = 38, color = 0xffff8822);
         // Main moon
-        let moon_color = if self.level >= 3 { 0xffddaaff } else { 0xfff8e0ff };
+        let moon_color = if self.sim.level >= 3 { 0xffddaaff } else { 0xfff8e0ff };
         circ!(x = moon_x, y = moon_y, d = 28, color = moon_color);
         circ!(x = moon_x + 4, y = moon_y - 2, d = 22, color = sky_color); // Crescent shadow
         
         // Mountains (mid layer) - larger for bigger screen
-        let mountain_offset = (self.scroll_x * 0.2) as i32 % 180;
+        let mountain_offset = (self.sim.scroll_x * 0.2) as i32 % 180;
         for i in 0..4 {
             let mx = i * 180 - mountain_offset + shake_x;
             let my = 110 + shake_y;
             // Larger mountains
-            let mountain_color = if self.level >= 4 { 0x1a2040ff } else { 0x2a3f5fff };
+            let mountain_color = if self.sim.level >= 4 { 0x1a2040ff } else { 0x2a3f5fff };
             for row in 0..60 {
                 let width = row * 4;
                 rect!(x = mx + 90 - width / 2, y = my + row, w = width as u32, h = 1, color = mountain_color);
@@ -1071,15 +1023,15 @@ impl GameState {
         // Pine trees with VARIATION (3 different styles)
         // Fixed: Use spacing that matches tree count for seamless scrolling
         let tree_spacing = 60;
-        let tree_offset = (self.scroll_x * 0.5) as i32 % tree_spacing;
+        let tree_offset = (self.sim.scroll_x * 0.5) as i32 % tree_spacing;
         for i in 0..10 {  // More trees to cover screen + buffer
             let tx = i * tree_spacing - tree_offset + shake_x;
             let tree_style = i % 3; // 3 different tree styles
             let size_mult = match i % 4 { 0 => 1.2, 1 => 0.8, 2 => 1.0, _ => 0.9 };
             let ty = 155 + shake_y + if i % 2 == 0 { 0 } else { 5 }; // Slight Y variation
             
-            let tree_green = if self.level >= 3 { 0x1a4a2aff } else { 0x2a6a3aff };
-            let tree_dark = if self.level >= 3 { 0x0f2a1aff } else { 0x1a4a2aff };
+            let tree_green = if self.sim.level >= 3 { 0x1a4a2aff } else { 0x2a6a3aff };
+            let tree_dark = if self.sim.level >= 3 { 0x0f2a1aff } else { 0x1a4a2aff };
             
             match tree_style {
                 0 => {
@@ -1132,9 +1084,22 @@ impl GameState {
         
         // Snow mounds (decorative)
         for i in 0..8 {
-            let mound_x = (i * 60 + 20) as i32 - ((self.scroll_x * 0.4) as i32 % 60) + shake_x;
+            let mound_x = (i * 60 + 20) - ((self.sim.scroll_x * 0.4) as i32 % 60) + shake_x;
             ellipse!(x = mound_x, y = ground_y + 10 + shake_y, w = 30 + (i % 3) as u32 * 10, h = 10, color = 0xf8f8ffff);
         }
+        
+        // Rivers under the endless street, with a little bridge across each
+        for river in &self.sim.rivers {
+            let rx = river.x as i32 + shake_x;
+            let rw = river.width as u32;
+            rect!(x = rx, y = ground_y + shake_y, w = rw, h = 50, color = 0x1a3a6aff);
+            for i in 0..(rw / 24) {
+                let wave = ((self.frame as f32 / 10.0 + i as f32).sin() * 3.0) as i32;
+                rect!(x = rx + 6 + i as i32 * 24 + wave, y = ground_y + 14 + (i % 3) as i32 * 9 + shake_y, w = 10, h = 1, color = 0x6a9acaff);
+            }
+            rect!(x = rx - 4, y = ground_y - 3 + shake_y, w = rw + 8, h = 4, color = 0x5a4030ff);
+            rect!(x = rx - 4, y = ground_y - 4 + shake_y, w = rw + 8, h = 1, color = 0xf8f8ffff);
+        }
     }
     
     fn draw_snowflakes(&self) {
@@ -1222,6 +1187,60 @@ impl GameState {
                 rect!(x = cx + 10, y = cy - 28, w = 12, h = 30, color = 0x555555ff);
                 rect!(x = cx + 8, y = cy - 30, w = 16, h = 4, color = 0x444444ff);
             }
+            3 => {
+                // STYLE 3: Village church (stone, spire on the left)
+                rect!(x = cx - 46, y = cy + 6, w = 92, h = 40, color = 0x6a6258ff);
+                rect!(x = cx - 43, y = cy + 8, w = 86, h = 36, color = 0x7a7268ff);
+                
+                // Stained glass windows
+                for wx in [cx - 24, cx - 6, cx + 12, cx + 30] {
+                    circ!(x = wx, y = cy + 18, d = 10, color = 0x4466ccff);
+                    rect!(x = wx - 5, y = cy + 18, w = 10, h = 12, color = 0x4466ccff);
+                    rect!(x = wx - 3, y = cy + 16, w = 6, h = 8, color = 0xcc4466ff);
+                }
+                
+                // Low pitched roof
+                for row in 0..13 {
+                    let width = 100 - row * 8;
+                    rect!(x = cx - width / 2, y = cy + 6 - row, w = width as u32, h = 1, color = 0x3a3a4aff);
+                }
+                rect!(x = cx - 48, y = cy + 3, w = 96, h = 3, color = 0xf8f8ffff);
+                
+                // Bell tower and spire
+                rect!(x = cx - 44, y = cy - 44, w = 12, h = 90, color = 0x5a5248ff);
+                rect!(x = cx - 41, y = cy - 34, w = 6, h = 8, color = 0xffdd66ff);
+                for row in 0..12 {
+                    let width = 14 - row;
+                    rect!(x = cx - 38 - width / 2, y = cy - 44 - row, w = width as u32, h = 1, color = 0x3a3a4aff);
+                }
+                rect!(x = cx - 39, y = cy - 64, w = 2, h = 9, color = COLOR_GOLD);
+                rect!(x = cx - 41, y = cy - 61, w = 6, h = 2, color = COLOR_GOLD);
+                
+                // Vestry chimney
+                rect!(x = cx - 5, y = cy - 14, w = 10, h = 16, color = 0x555555ff);
+                rect!(x = cx - 7, y = cy - 16, w = 14, h = 4, color = 0x444444ff);
+            }
+            4 => {
+                // STYLE 4: One section of an apartment block (brick, flat roof)
+                rect!(x = cx - 18, y = cy + 4, w = 36, h = 60, color = 0x6a3a30ff);
+                rect!(x = cx - 18, y = cy + 4, w = 1, h = 60, color = 0x4a2a20ff);
+                
+                // Windows, some lights off
+                for row in 0..3 {
+                    for col in 0..2 {
+                        let lit = !(chimney.id + row * 2 + col).is_multiple_of(3);
+                        let color = if lit { 0xffdd66ff } else { 0x2a2a3aff };
+                        rect!(x = cx - 12 + col as i32 * 16, y = cy + 12 + row as i32 * 16, w = 8, h = 10, color = color);
+                    }
+                }
+                
+                // Snow on the flat roof
+                rect!(x = cx - 18, y = cy + 2, w = 36, h = 3, color = 0xf8f8ffff);
+                
+                // Chimney
+                rect!(x = cx - 4, y = cy - 12, w = 8, h = 16, color = 0x555555ff);
+                rect!(x = cx - 6, y = cy - 14, w = 12, h = 4, color = 0x444444ff);
+            }
             _ => {
                 // STYLE 2: Wide mansion (gray stone, elegant)
                 // House base (wider)
@@ -1262,35 +1281,73 @@ impl GameState {
         let chimney_x = match chimney.style {
             0 => cx + 15,
             1 => cx + 16,
+            3 | 4 => cx,
             _ => cx - 23, // Left chimney for mansion
         };
         let chimney_y = match chimney.style {
             0 => cy - 12,
             1 => cy - 25,
+            3 => cy - 12,
             _ => cy - 10,
         };
         
+        // Naughty houses sit under a red cast; visited ones have their lights out
+        let (body_x, body_y, body_w) = match chimney.style {
+            0 => (cx - 25, cy + 10, 50),
+            1 => (cx - 22, cy + 5, 44),
+            3 => (cx - 46, cy + 6, 92),
+            4 => (cx - 18, cy + 4, 36),
+            _ => (cx - 35, cy + 8, 70),
+        };
+        // Versus: whose lane the house is in
+        if let Some(lane) = chimney.lane {
+            let color = PLAYER_COLORS[lane as usize % PLAYER_COLORS.len()];
+            rect!(x = body_x, y = body_y + 33, w = body_w as u32, h = 3, color = color);
+            text!("P{}", lane + 1; x = body_x + 3, y = body_y + 25, font = "small", color = color);
+        }
+        match chimney.house {
+            HouseKind::Nice => {}
+            HouseKind::Naughty => {
+                rect!(x = body_x, y = body_y, w = body_w as u32, h = 36, color = 0x66000066);
+                // Lump of coal hung on the door
+                circ!(x = cx, y = body_y + 20, d = 7, color = 0x222222ff);
+            }
+            HouseKind::Visited => {
+                rect!(x = body_x, y = body_y, w = body_w as u32, h = 36, color = 0x000022aa);
+                return;
+            }
+        }
+        
         if !chimney.delivered {
             let pulse = ((self.frame as f32 / 8.0).sin() * 30.0) as u32;
-            let glow_color = 0xffff0000 + (pulse << 24);
-            circ!(x = chimney_x, y = chimney_y, d = 22 + (pulse / 8) as u32, color = glow_color);
+            let tint = if chimney.house == HouseKind::Naughty { 0xff220000 } else { 0xffff0000 };
+            let glow_color = tint + (pulse << 24);
+            circ!(x = chimney_x, y = chimney_y, d = 22 + (pulse / 8), color = glow_color);
+            
+            // Speech bubble with the gift this house asked for
+            if let Some(kind) = chimney.request {
+                let (bx, by) = (chimney_x, chimney_y - 34);
+                rect!(x = bx - 12, y = by - 12, w = 24, h = 22, color = 0xffffffdd);
+                rect!(x = bx - 2, y = by + 10, w = 4, h = 4, color = 0xffffffdd);
+                self.draw_gift(kind, bx, by - 1);
+            }
         } else {
             circ!(x = chimney_x, y = chimney_y, d = 18, color = 0x00ff0088);
         }
     }
     
-    fn draw_sleigh(&self, shake_x: i32, shake_y: i32) {
+    fn draw_sleigh(&self, sleigh: &Player, player_y: f32, shake_x: i32, shake_y: i32) {
         // Blink when invincible (don't draw every other frame)
-        if self.invincible_timer > 0 && (self.frame / 4) % 2 == 0 {
+        if sleigh.invincible_timer > 0 && (self.frame / 4).is_multiple_of(2) {
             return; // Skip drawing for blink effect
         }
         
-        let x = PLAYER_X as i32 + shake_x;
-        let y = self.player_y as i32 + shake_y;
-        let tilt = self.sleigh_tilt as i32;
+        let x = sleigh.x as i32 + shake_x;
+        let y = player_y as i32 + shake_y;
+        let tilt = sleigh.tilt as i32;
         
         // Invincibility glow
-        if self.invincible_timer > 0 {
+        if sleigh.invincible_timer > 0 {
             circ!(x = x + 24, y = y + 4, d = 55, color = 0xffffff33);
         }
         
@@ -1383,32 +1440,159 @@ impl GameState {
     }
     
     fn draw_falling_gift(&self, gift: &FallingGift, shake_x: i32, shake_y: i32) {
-        let x = gift.x as i32 + shake_x;
-        let y = gift.y as i32 + shake_y;
-        
-        // Gift box with ribbon (detailed)
-        rect!(x = x - 7, y = y - 7, w = 14, h = 14, color = 0xff0000ff);
-        rect!(x = x - 6, y = y - 6, w = 12, h = 12, color = 0xcc0000ff);
-        // Ribbon
-        rect!(x = x - 1, y = y - 7, w = 3, h = 14, color = COLOR_GOLD);
-        rect!(x = x - 7, y = y - 1, w = 14, h = 3, color = COLOR_GOLD);
-        // Bow
-        circ!(x = x - 2, y = y - 5, d = 4, color = COLOR_GOLD);
-        circ!(x = x + 2, y = y - 5, d = 4, color = COLOR_GOLD);
+        let x = lerp(gift.prev_x, gift.x, self.render_alpha) as i32 + shake_x;
+        let y = lerp(gift.prev_y, gift.y, self.render_alpha) as i32 + shake_y;
+        self.draw_gift(gift.kind, x, y);
     }
     
-    fn draw_krampus(&self, shake_x: i32, shake_y: i32) {
-        if !self.krampus_active { return; }
-        
-        let x = self.krampus_x as i32 + shake_x;
-        let y = self.krampus_y as i32 + shake_y;
+    /// One gift of the given kind centred on (x, y)
+    fn draw_gift(&self, kind: GiftKind, x: i32, y: i32) {
+        match kind {
+            GiftKind::Present => {
+                // Gift box with ribbon (detailed)
+                rect!(x = x - 7, y = y - 7, w = 14, h = 14, color = 0xff0000ff);
+                rect!(x = x - 6, y = y - 6, w = 12, h = 12, color = 0xcc0000ff);
+                // Ribbon
+                rect!(x = x - 1, y = y - 7, w = 3, h = 14, color = COLOR_GOLD);
+                rect!(x = x - 7, y = y - 1, w = 14, h = 3, color = COLOR_GOLD);
+                // Bow
+                circ!(x = x - 2, y = y - 5, d = 4, color = COLOR_GOLD);
+                circ!(x = x + 2, y = y - 5, d = 4, color = COLOR_GOLD);
+            }
+            GiftKind::Light => {
+                // Small pastel parcel on a balloon string
+                rect!(x = x - 5, y = y - 3, w = 10, h = 10, color = 0x88ccffff);
+                rect!(x = x - 1, y = y - 3, w = 2, h = 10, color = 0xffffffff);
+                rect!(x = x, y = y - 9, w = 1, h = 6, color = 0xffffffaa);
+                circ!(x = x, y = y - 11, d = 6, color = 0xff88ccff);
+            }
+            GiftKind::Heavy => {
+                // Big iron-banded crate
+                rect!(x = x - 8, y = y - 8, w = 16, h = 16, color = 0x6a4a2aff);
+                rect!(x = x - 7, y = y - 7, w = 14, h = 14, color = 0x8b5a2bff);
+                rect!(x = x - 8, y = y - 4, w = 16, h = 2, color = 0x444444ff);
+                rect!(x = x - 8, y = y + 3, w = 16, h = 2, color = 0x444444ff);
+            }
+            GiftKind::Fragile => {
+                // Glass bauble in an open-topped box
+                circ!(x = x, y = y - 4, d = 10, color = 0xaaddffcc);
+                circ!(x = x - 2, y = y - 6, d = 3, color = 0xffffffff);
+                rect!(x = x - 6, y = y, w = 12, h = 7, color = 0x44aa66ff);
+                rect!(x = x - 6, y = y + 2, w = 12, h = 1, color = 0xffffffaa);
+            }
+            GiftKind::Coal => {
+                // Lump of coal
+                circ!(x = x, y = y, d = 11, color = 0x222222ff);
+                circ!(x = x - 2, y = y - 2, d = 4, color = 0x444444ff);
+                circ!(x = x + 3, y = y + 1, d = 3, color = 0x333333ff);
+            }
+        }
+    }
+    
+    /// The gift type each player's next drop will be, on the ground strip
+    fn draw_gift_selector(&self, shake_x: i32, shake_y: i32) {
+        for (i, player) in self.sim.players.iter().enumerate() {
+            if !player.flying() { continue; }
+            let (x, y) = (8 + i as i32 * 92 + shake_x, SCREEN_H as i32 - 26 + shake_y);
+            rect!(x = x, y = y, w = 84, h = 22, color = 0x000000aa);
+            self.draw_gift(player.gift_kind, x + 11, y + 12);
+            text!("{}", player.gift_kind.name(); x = x + 24, y = y + 4, font = "small", color = 0xffffffff);
+            let hint = if i == 0 { "TAB: next" } else { "X: next" };
+            text!("{}", hint; x = x + 24, y = y + 13, font = "small", color = 0x888888ff);
+        }
+    }
+    
+    fn draw_enemy(&self, enemy: &Enemy, shake_x: i32, shake_y: i32) {
+        let x = lerp(enemy.prev_x, enemy.x, self.render_alpha) as i32 + shake_x;
+        let y = lerp(enemy.prev_y, enemy.y, self.render_alpha) as i32 + shake_y;
+        match enemy.kind {
+            EnemyKind::Krampus => self.draw_krampus(x, y),
+            EnemyKind::Minion => self.draw_minion(x, y),
+            EnemyKind::CoalThrower => self.draw_coal_thrower(enemy, x, y),
+            EnemyKind::SnowGolem => self.draw_snow_golem(enemy, x, y),
+        }
+    }
+    
+    /// Small horned imp with bat wings
+    fn draw_minion(&self, x: i32, y: i32) {
+        let flap = ((self.frame as f32 / 3.0).sin() * 5.0) as i32;
+        rect!(x = x - 2, y = y - 6 + flap, w = 12, h = 6, color = 0x1a0a0aff);
+        rect!(x = x - 2, y = y - flap, w = 12, h = 6, color = 0x1a0a0aff);
+        circ!(x = x, y = y, d = 14, color = 0x3a2020ff);
+        rect!(x = x - 5, y = y - 11, w = 2, h = 5, color = 0x5a4040ff);
+        rect!(x = x + 3, y = y - 11, w = 2, h = 5, color = 0x5a4040ff);
+        circ!(x = x - 3, y = y - 1, d = 3, color = 0xff0000ff);
+        circ!(x = x + 2, y = y - 1, d = 3, color = 0xff0000ff);
+    }
+    
+    /// Hunched green figure on a roof with a sack of coal
+    fn draw_coal_thrower(&self, enemy: &Enemy, x: i32, y: i32) {
+        // Wind up just before a throw
+        let arm = if enemy.timer < 15 { -8 } else { 0 };
+        rect!(x = x + 4, y = y - 4, w = 10, h = 10, color = 0x3a3a3aff); // Sack
+        rect!(x = x - 6, y = y - 10, w = 12, h = 16, color = 0x4a7a2aff);
+        circ!(x = x, y = y - 14, d = 12, color = 0x5a9a3aff);
+        circ!(x = x - 2, y = y - 15, d = 3, color = 0xffee00ff);
+        circ!(x = x + 2, y = y - 15, d = 3, color = 0xffee00ff);
+        rect!(x = x - 3, y = y - 11, w = 6, h = 1, color = 0x1a3a0aff);
+        rect!(x = x - 9, y = y - 8 + arm, w = 3, h = 8, color = 0x4a7a2aff);
+        if arm != 0 {
+            circ!(x = x - 8, y = y - 18, d = 6, color = 0x222222ff);
+        }
+    }
+    
+    /// Three stacked snowballs sitting on a chimney
+    fn draw_snow_golem(&self, enemy: &Enemy, x: i32, y: i32) {
+        let cracked = enemy.health < enemy.kind.health();
+        circ!(x = x, y = y + 8, d = 20, color = COLOR_SNOW);
+        circ!(x = x, y = y - 4, d = 15, color = COLOR_SNOW);
+        circ!(x = x, y = y - 14, d = 11, color = COLOR_SNOW);
+        circ!(x = x - 2, y = y - 15, d = 2, color = 0x000000ff);
+        circ!(x = x + 2, y = y - 15, d = 2, color = 0x000000ff);
+        rect!(x = x, y = y - 14, w = 4, h = 2, color = 0xff8800ff); // Carrot
+        rect!(x = x - 12, y = y - 5, w = 6, h = 2, color = 0x6b3a10ff); // Stick arms
+        rect!(x = x + 6, y = y - 5, w = 6, h = 2, color = 0x6b3a10ff);
+        if cracked {
+            rect!(x = x - 3, y = y + 2, w = 1, h = 8, color = 0x8899aaff);
+            rect!(x = x - 2, y = y + 6, w = 5, h = 1, color = 0x8899aaff);
+        }
+    }
+    
+    /// Dotted arc to where a gift dropped now from each sleigh would land
+    fn draw_aim_guide(&self, shake_x: i32, shake_y: i32) {
+        for (i, player) in self.sim.players.iter().enumerate() {
+            if player.flying() {
+                self.draw_aim_path(i, shake_x, shake_y);
+            }
+        }
+    }
+    
+    fn draw_aim_path(&self, player: usize, shake_x: i32, shake_y: i32) {
+        let (path, lands_in_chimney) = self.sim.predict_drop(player);
+        let color = if lands_in_chimney { 0x66ff66aa } else { 0xffffff55 };
+        for &(x, y) in path.iter().step_by(3) {
+            rect!(x = x as i32 + shake_x, y = y as i32 + shake_y, w = 2, h = 2, color = color);
+        }
+        if let Some(&(x, y)) = path.last() {
+            let (x, y) = (x as i32 + shake_x, y as i32 + shake_y);
+            if lands_in_chimney {
+                circ!(x = x, y = y, d = 10, color = 0x66ff6688);
+            } else {
+                rect!(x = x - 3, y = y, w = 7, h = 1, color = 0xff6666aa);
+                rect!(x = x, y = y - 3, w = 1, h = 7, color = 0xff6666aa);
+            }
+        }
+    }
+    
+    fn draw_krampus(&self, x: i32, y: i32) {
         let shake = ((self.frame as f32 / 2.0).sin() * 3.0) as i32;
         let wing_flap = ((self.frame as f32 / 5.0).sin() * 8.0) as i32;
         
-        // Ominous red aura (pulsing)
+        // Ominous red aura (pulsing, growing as he gets angrier)
         let aura_pulse = ((self.frame as f32 / 8.0).sin() * 20.0) as u32;
-        circ!(x = x, y = y, d = 70 + aura_pulse, color = 0x44000022);
-        circ!(x = x, y = y, d = 55 + aura_pulse, color = 0x66000033);
+        let rage = (self.sim.krampus_stage.saturating_sub(1) as u32) * 12;
+        circ!(x = x, y = y, d = 70 + aura_pulse + rage, color = 0x44000022 + rage);
+        circ!(x = x, y = y, d = 55 + aura_pulse + rage, color = 0x66000033 + rage);
         
         // Wings/cape (flapping)
         rect!(x = x + 12, y = y - 18 + wing_flap / 2, w = 25, h = 30, color = 0x1a0a0aff);
@@ -1456,11 +1640,90 @@ impl GameState {
         // Claws
         rect!(x = x - 22 + shake, y = y + 10, w = 8, h = 10, color = 0x1a0a0aff);
         rect!(x = x + 14 + shake, y = y + 10, w = 8, h = 10, color = 0x1a0a0aff);
+        
+        // Blink white when a snowball lands
+        if self.krampus_hit_flash > 0 && (self.krampus_hit_flash / 2).is_multiple_of(2) {
+            circ!(x = x + shake, y = y - 4, d = 44, color = 0xffffff88);
+        }
+    }
+    
+    /// Every sleigh still flying, with a glow while star power lasts and a tag in co-op
+    fn draw_player(&self, shake_x: i32, shake_y: i32) {
+        let coop = self.sim.players.len() > 1;
+        for (i, player) in self.sim.players.iter().enumerate() {
+            if !player.flying() { continue; }
+            let player_y = lerp(player.prev_y, player.y, self.render_alpha);
+            if self.sim.has_powerup(PowerUpKind::Star) {
+                let glow_alpha = ((self.frame as f32 / 5.0).sin() * 50.0 + 150.0) as u32;
+                circ!(x = player.x as i32 + shake_x, y = player_y as i32 + shake_y, d = 50, color = 0xffff0000 | glow_alpha);
+            }
+            self.draw_sleigh(player, player_y, shake_x, shake_y);
+            if coop {
+                let (x, y) = (player.x as i32 + 6 + shake_x, player_y as i32 - 22 + shake_y);
+                text!("P{}", i + 1; x = x, y = y, font = "small", color = PLAYER_COLORS[i]);
+            }
+        }
+    }
+    
+    /// The raced run's sleighs, pale and see-through so they never hide the live ones
+    fn draw_ghost(&self, shake_x: i32, shake_y: i32) {
+        let Some(ghost) = &self.ghost else { return };
+        if ghost.finished() { return; }
+        for sleigh in ghost.sim.players.iter().filter(|p| p.flying()) {
+            let x = sleigh.x as i32 + shake_x;
+            let y = lerp(sleigh.prev_y, sleigh.y, self.render_alpha) as i32 + shake_y + sleigh.tilt as i32 / 2;
+            rect!(x = x - 6, y = y, w = 40, h = 17, color = 0xccddff44);           // Sleigh
+            rect!(x = x - 8, y = y + 17, w = 44, h = 3, color = 0xccddff33);       // Runner
+            circ!(x = x + 10, y = y - 4, d = 14, color = 0xeef4ff55);              // Santa
+            circ!(x = x + 26, y = y - 2, d = 12, color = 0xccddff33);              // Sack
+        }
+        if let Some(lead) = ghost.sim.players.iter().find(|p| p.flying()) {
+            let y = lerp(lead.prev_y, lead.y, self.render_alpha) as i32;
+            text!("GHOST", x = lead.x as i32 - 2 + shake_x, y = y - 22 + shake_y, font = "small", color = 0xccddff88);
+        }
+    }
+    
+    fn draw_snowball(&self, ball: &Snowball, shake_x: i32, shake_y: i32) {
+        let x = lerp(ball.prev_x, ball.x, self.render_alpha) as i32 + shake_x;
+        let y = lerp(ball.prev_y, ball.y, self.render_alpha) as i32 + shake_y;
+        circ!(x = x - 5, y = y, d = 5, color = 0xffffff44); // Trail
+        circ!(x = x, y = y, d = 8, color = COLOR_SNOW);
+        circ!(x = x + 1, y = y - 1, d = 3, color = 0xffffffff);
+    }
+    
+    /// Boss health bar along the bottom of the screen
+    fn draw_krampus_health(&self) {
+        let Some(krampus) = self.sim.krampus() else { return };
+        if self.sim.krampus_max_health == 0 { return; }
+        
+        let (x, y, w) = (232, 202, 140u32);
+        let filled = w * krampus.health / self.sim.krampus_max_health;
+        let color = match self.sim.krampus_stage {
+            1 => 0xcc3333ff,
+            2 => 0xff6600ff,
+            _ => 0xff0000ff,
+        };
+        text!("KRAMPUS", x = x, y = y - 10, font = "small", color = color);
+        rect!(x = x - 1, y = y - 1, w = w + 2, h = 8, color = 0x000000ff);
+        rect!(x = x, y = y, w = w, h = 6, color = 0x331111ff);
+        rect!(x = x, y = y, w = filled, h = 6, color = color);
+        // Stage marks at two thirds and one third
+        rect!(x = x + (w / 3) as i32, y = y, w = 1, h = 6, color = 0x000000ff);
+        rect!(x = x + (w * 2 / 3) as i32, y = y, w = 1, h = 6, color = 0x000000ff);
     }
     
     fn draw_projectile(&self, proj: &Projectile, shake_x: i32, shake_y: i32) {
-        let x = proj.x as i32 + shake_x;
-        let y = proj.y as i32 + shake_y;
+        let x = lerp(proj.prev_x, proj.x, self.render_alpha) as i32 + shake_x;
+        let y = lerp(proj.prev_y, proj.y, self.render_alpha) as i32 + shake_y;
+        
+        if proj.source == EnemyKind::CoalThrower {
+            // Lump of coal with glowing embers
+            circ!(x = x, y = y, d = 12, color = 0xff440033);
+            circ!(x = x, y = y, d = 8, color = 0x222222ff);
+            circ!(x = x - 1, y = y + 1, d = 2, color = 0xff6600ff);
+            circ!(x = x + 2, y = y - 1, d = 2, color = 0xffaa00ff);
+            return;
+        }
         
         // Flame trail (glow circles)
         for i in 1..=4i32 {
@@ -1477,50 +1740,121 @@ impl GameState {
         circ!(x = x, y = y, d = 5, color = 0xffcc00ff);  // Hot center
     }
     
-    fn draw_ui(&self, shake_x: i32, shake_y: i32) {
-        // Health hearts (detailed)
+    /// A row of three health hearts
+    fn draw_hearts(&self, health: u32, x: i32, y: i32) {
         for i in 0..3 {
-            let hx = 12 + i * 22 + shake_x as u32;
-            let hy = 12 + shake_y;
-            let color = if i < self.health { 0xff0000ff } else { 0x444444ff };
-            let highlight = if i < self.health { 0xff6666ff } else { 0x555555ff };
+            let hx = x + i * 22;
+            let color = if (i as u32) < health { 0xff0000ff } else { 0x444444ff };
+            let highlight = if (i as u32) < health { 0xff6666ff } else { 0x555555ff };
             // Heart shape
-            circ!(x = hx as i32 - 3, y = hy, d = 10, color = color);
-            circ!(x = hx as i32 + 3, y = hy, d = 10, color = color);
-            rect!(x = hx as i32 - 8, y = hy, w = 16, h = 8, color = color);
+            circ!(x = hx - 3, y = y, d = 10, color = color);
+            circ!(x = hx + 3, y = y, d = 10, color = color);
+            rect!(x = hx - 8, y = y, w = 16, h = 8, color = color);
             // Point of heart
             for row in 0..6 {
                 let w = 16 - row * 3;
                 if w > 0 {
-                    rect!(x = hx as i32 - w as i32 / 2, y = hy + 6 + row, w = w as u32, h = 1, color = color);
+                    rect!(x = hx - w / 2, y = y + 6 + row, w = w as u32, h = 1, color = color);
                 }
             }
             // Highlight
-            circ!(x = hx as i32 - 4, y = hy - 2, d = 4, color = highlight);
+            circ!(x = hx - 4, y = y - 2, d = 4, color = highlight);
+        }
+    }
+    
+    fn draw_ui(&self, shake_x: i32, shake_y: i32) {
+        // Health hearts (detailed), a row per player in co-op
+        let coop = self.sim.players.len() > 1;
+        for (i, player) in self.sim.players.iter().enumerate() {
+            let y = 12 + i as i32 * 18 + shake_y;
+            self.draw_hearts(player.health, 12 + shake_x, y);
+            if coop {
+                text!("P{}", i + 1; x = 80 + shake_x, y = y - 2, font = "small", color = PLAYER_COLORS[i]);
+            }
+        }
+        
+        self.draw_active_powerups(34 + (self.sim.players.len() as i32 - 1) * 18);
+        if matches!(self.sim.mode, GameMode::Delivering | GameMode::KrampusWarning) {
+            self.draw_gift_selector(shake_x, shake_y);
+        }
+        
+        // Score (the team's, and each player's own share in co-op)
+        text!("SCORE: {}", self.sim.score; x = 180 + shake_x, y = 4 + shake_y, font = "small", color = COLOR_GOLD);
+        if coop {
+            let versus = self.sim.run_mode == RunMode::Versus;
+            for (i, player) in self.sim.players.iter().enumerate() {
+                let y = 4 + i as i32 * 10 + shake_y;
+                if versus {
+                    text!("P{} {}/{}", i + 1, player.deliveries, VERSUS_TARGET; x = 270 + shake_x, y = y, font = "small", color = PLAYER_COLORS[i]);
+                } else {
+                    text!("P{} {}", i + 1, player.score; x = 270 + shake_x, y = y, font = "small", color = PLAYER_COLORS[i]);
+                }
+            }
+        }
+        
+        // Ghost race: how far ahead of the ghost's score at this point in its run
+        if let Some(ghost) = &self.ghost {
+            let delta = ghost.delta(self.sim.score);
+            let color = if delta >= 0 { 0x66ff66ff } else { 0xff6666ff };
+            text!("GHOST {:+}", delta; x = 270 + shake_x, y = 24 + shake_y, font = "small", color = color);
+        }
+        
+        // Versus: what just got sent across
+        if let Some((from, naughty, _)) = self.attack_notice {
+            let what = if naughty { "a naughty house" } else { "Krampus fire" };
+            let color = PLAYER_COLORS[from as usize % PLAYER_COLORS.len()];
+            text!("P{} sent {}!", from + 1, what; x = 130, y = 46, font = "small", color = color);
         }
         
-        // Score
-        text!("SCORE: {}", self.score; x = 180 + shake_x, y = 4 + shake_y, font = "small", color = COLOR_GOLD);
+        // Deliveries (against the target on a campaign night, with how far along the route)
+        if let Some(night) = self.sim.night() {
+            let color = if self.sim.deliveries >= night.target { 0x00ff00ff } else { 0xffffffff };
+            text!("Gifts: {}/{}", self.sim.deliveries, night.target; x = 180 + shake_x, y = 14 + shake_y, font = "small", color = color);
+            let (x, y) = (180 + shake_x, 26 + shake_y);
+            let progress = (self.sim.distance / night.route).min(1.0);
+            rect!(x = x, y = y, w = 60, h = 4, color = 0x333333ff);
+            rect!(x = x, y = y, w = (progress * 60.0) as u32, h = 4, color = 0xaaccffff);
+            for &at in &night.krampus {
+                rect!(x = x + (at * 60.0) as i32, y = y - 1, w = 1, h = 6, color = 0xff4400ff);
+            }
+        } else {
+            text!("Gifts: {}", self.sim.deliveries; x = 180 + shake_x, y = 14 + shake_y, font = "small", color = 0x00ff00ff);
+        }
         
-        // Deliveries
-        text!("Gifts: {}", self.deliveries; x = 180 + shake_x, y = 14 + shake_y, font = "small", color = 0x00ff00ff);
+        // Distance along the endless street
+        if self.sim.run_mode == RunMode::Endless {
+            text!("{}m", self.sim.distance as u32 / 10; x = 180 + shake_x, y = 24 + shake_y, font = "small", color = 0xaaccffff);
+        }
         
         // Level
-        text!("Lv.{}", self.level; x = 120 + shake_x, y = 4 + shake_y, font = "small", color = 0xffffffff);
+        text!("Lv.{}", self.sim.level; x = 120 + shake_x, y = 4 + shake_y, font = "small", color = 0xffffffff);
+        
+        // Wind direction and strength
+        let wind = self.sim.wind();
+        if wind.abs() > 0.002 {
+            let arrows = ((wind.abs() / 0.01).ceil() as usize).min(4);
+            let label = if wind > 0.0 { ">".repeat(arrows) } else { "<".repeat(arrows) };
+            text!("Wind {}", label; x = 120 + shake_x, y = 14 + shake_y, font = "small", color = 0xaaccffff);
+        }
         
         // Naughty meter (if > 0)
-        if self.naughty_meter > 0 {
+        if self.sim.naughty_meter > 0 {
             rect!(x = 60 + shake_x, y = 136 + shake_y, w = 50, h = 6, color = 0x333333ff);
-            let bar_w = (self.naughty_meter as u32 * 50 / 100).min(50);
-            let bar_color = if self.naughty_meter > 60 { 0xff0000ff } else { 0xffaa00ff };
+            let bar_w = (self.sim.naughty_meter * 50 / 100).min(50);
+            let bar_color = if self.sim.naughty_meter > 60 { 0xff0000ff } else { 0xffaa00ff };
             rect!(x = 60 + shake_x, y = 136 + shake_y, w = bar_w, h = 6, color = bar_color);
             text!("NAUGHTY", x = 60 + shake_x, y = 128 + shake_y, font = "small", color = bar_color);
         }
         
         // Krampus warning
-        if self.krampus_warning > 0 && (self.frame / 8) % 2 == 0 {
+        if self.sim.krampus_warning > 0 && (self.frame / 8).is_multiple_of(2) {
             text!("!! KRAMPUS COMING !!", x = 60, y = 60, font = "medium", color = 0xff0000ff);
         }
+        
+        // Replay indicator
+        if self.playback.is_some() && (self.frame / 30).is_multiple_of(2) {
+            text!("REPLAY", x = 340, y = 4, font = "small", color = COLOR_CANDY);
+        }
     }
     
     /// Draw tutorial overlay during first game
@@ -1535,7 +1869,7 @@ impl GameState {
         let step = (time_elapsed / 150) as u8; // 4 steps over 10 seconds
         
         // Blinking effect for emphasis
-        let show_text = (self.frame / 15) % 2 == 0;
+        let show_text = (self.frame / 15).is_multiple_of(2);
         
         match step {
             0 => {
@@ -1612,10 +1946,140 @@ impl GameState {
         
         text!("PAUSED", x = 155, y = 85, font = "large", color = 0xffffffff);
         
-        text!("ESC to Resume", x = 145, y = 115, font = "small", color = 0xaaaaaaff);
+        text!("ESC to Resume", x = 145, y = 108, font = "small", color = 0xaaaaaaff);
+        text!("O / X for Options", x = 140, y = 119, font = "small", color = 0xaaaaaaff);
         
         // Show current stats
-        text!("Score: {}", self.score; x = 155, y = 130, font = "small", color = COLOR_GOLD);
+        text!("Score: {}", self.sim.score; x = 155, y = 130, font = "small", color = COLOR_GOLD);
+    }
+    
+    // ========================================================================
+    // LEADERBOARD
+    // ========================================================================
+    
+    fn draw_menu(&self) {
+        match self.menu {
+            Menu::None => {}
+            Menu::Leaderboard => self.draw_leaderboard(),
+            Menu::Options => self.draw_options(),
+        }
+    }
+    
+    fn draw_options(&self) {
+        rect!(x = 0, y = 0, w = SCREEN_W as u32, h = SCREEN_H as u32, color = 0x000000dd);
+        rect!(x = 92, y = 24, w = 200, h = 168, color = 0x111122ff);
+        
+        text!("OPTIONS", x = 156, y = 34, font = "large", color = COLOR_GOLD);
+        
+        let settings = &self.save.settings;
+        let percent = |v: f32| format!("{}%", (v * 100.0).round() as u32);
+        for (i, &label) in OPTIONS.iter().enumerate() {
+            let y = 60 + i as i32 * 13;
+            let selected = i == self.options_cursor;
+            if selected {
+                rect!(x = 98, y = y - 3, w = 188, h = 12, color = 0x333355ff);
+            }
+            let color = if selected { 0xffffffff } else { 0xaaaaaaff };
+            text!("{}", label; x = 106, y = y, font = "small", color = color);
+            
+            let value = match label {
+                "Music" => percent(settings.music_volume),
+                "Sound FX" => percent(settings.sfx_volume),
+                "Screen shake" => if settings.screen_shake { "On".to_string() } else { "Off".to_string() },
+                "Aim guide" => if settings.aim_guide { "On".to_string() } else { "Off".to_string() },
+                "Players" => if settings.players > 1 { "2 (co-op)".to_string() } else { "1".to_string() },
+                "Lives" => if settings.shared_lives { "Shared".to_string() } else { "Separate".to_string() },
+                "Flash" => percent(settings.flash_intensity),
+                "Reset tutorial" if self.tutorial_reset => "Done".to_string(),
+                _ => String::new(),
+            };
+            if !value.is_empty() {
+                text!("< {} >", value; x = 220, y = y, font = "small", color = color);
+            }
+        }
+        
+        text!("LEFT/RIGHT change  ESC / B back", x = 112, y = 178, font = "small", color = 0x666666ff);
+    }
+    
+    fn draw_leaderboard(&self) {
+        rect!(x = 0, y = 0, w = SCREEN_W as u32, h = SCREEN_H as u32, color = 0x000000dd);
+        rect!(x = 62, y = 12, w = 260, h = 192, color = 0x111122ff);
+        
+        text!("TOP 10", x = 160, y = 20, font = "large", color = COLOR_GOLD);
+        
+        let header = 0x888888ff;
+        text!("#", x = 74, y = 44, font = "small", color = header);
+        text!("NAME", x = 94, y = 44, font = "small", color = header);
+        text!("SCORE", x = 134, y = 44, font = "small", color = header);
+        text!("GIFTS", x = 190, y = 44, font = "small", color = header);
+        text!("COMBO", x = 234, y = 44, font = "small", color = header);
+        text!("LV", x = 284, y = 44, font = "small", color = header);
+        
+        if self.save.leaderboard.is_empty() {
+            text!("No scores yet - go deliver!", x = 100, y = 100, font = "small", color = 0xaaaaaaff);
+        }
+        
+        for (i, entry) in self.save.leaderboard.iter().enumerate() {
+            let y = 58 + i as i32 * 13;
+            let highlighted = self.highlight_rank == Some(i);
+            if highlighted {
+                rect!(x = 68, y = y - 3, w = 248, h = 12, color = 0x44330088);
+            }
+            let color = match i {
+                0 => COLOR_GOLD,
+                _ if highlighted => 0xffffffff,
+                _ => 0xccccccff,
+            };
+            text!("{}", i + 1; x = 74, y = y, font = "small", color = color);
+            text!("{}", entry.initials(); x = 94, y = y, font = "small", color = color);
+            text!("{}", entry.score; x = 134, y = y, font = "small", color = color);
+            text!("{}", entry.deliveries; x = 190, y = y, font = "small", color = color);
+            text!("x{}", entry.max_combo; x = 234, y = y, font = "small", color = color);
+            text!("{}", entry.level; x = 284, y = y, font = "small", color = color);
+        }
+        
+        text!("ESC / B to close", x = 140, y = 192, font = "small", color = 0x666666ff);
+    }
+    
+    /// Initials picker shown on game over when a run makes the top 10
+    fn draw_initials_entry(&self, entry: &InitialsEntry) {
+        text!("RANK #{}! Enter your initials", entry.rank + 1; x = 56, y = 118, font = "small", color = COLOR_GOLD);
+        
+        for (i, &letter) in entry.letters.iter().enumerate() {
+            let x = 70 + i as i32 * 22;
+            let selected = i == entry.cursor;
+            rect!(x = x - 3, y = 131, w = 18, h = 20, color = if selected { 0x333355ff } else { 0x222222ff });
+            text!("{}", letter as char; x = x, y = 134, font = "large", color = if selected { 0xffffffff } else { 0xaaaaaaff });
+            if selected && (self.frame / 15).is_multiple_of(2) {
+                rect!(x = x - 3, y = 152, w = 18, h = 2, color = COLOR_GOLD);
+            }
+        }
+        
+        text!("UP/DOWN letter  LEFT/RIGHT move  A next", x = 56, y = 160, font = "small", color = 0x888888ff);
+    }
+    
+    fn draw_ghost_import(&self, import: &GhostImport) {
+        rect!(x = 0, y = 0, w = SCREEN_W as u32, h = SCREEN_H as u32, color = 0x000000dd);
+        rect!(x = 32, y = 40, w = 320, h = 136, color = 0x111122ff);
+        
+        text!("IMPORT GHOST", x = 144, y = 50, font = "large", color = COLOR_GOLD);
+        text!("Type or paste the hex of a shared ghost or replay", x = 44, y = 74, font = "small", color = 0xaaaaaaff);
+        
+        // The last few lines of what has come in so far
+        let per_line = 48;
+        let lines: Vec<&str> = import.hex.as_bytes()
+            .chunks(per_line)
+            .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+            .collect();
+        for (i, line) in lines[lines.len().saturating_sub(4)..].iter().enumerate() {
+            text!("{}", line; x = 44, y = 90 + i as i32 * 10, font = "small", color = 0xffffffff);
+        }
+        
+        text!("{} bytes", import.hex.len() / 2; x = 44, y = 136, font = "small", color = 0x888888ff);
+        if let Some(err) = &import.error {
+            text!("{}", err; x = 44, y = 148, font = "small", color = 0xff6666ff);
+        }
+        text!("ENTER load  BACKSPACE delete  ESC cancel", x = 44, y = 162, font = "small", color = 0x666666ff);
     }
     
     // ========================================================================
@@ -1630,6 +2094,19 @@ impl GameState {
             let py = particle.y as i32 + shake_y;
             circ!(x = px, y = py, d = particle.size, color = color);
         }
+        
+        for popup in &self.grade_popups {
+            let alpha = ((popup.life as f32 / 20.0) * 255.0).min(255.0) as u32;
+            let color = match popup.grade {
+                HitGrade::Perfect => COLOR_GOLD,
+                HitGrade::Great => 0x66ccffff,
+                HitGrade::Good => 0x44ff44ff,
+            };
+            let name = popup.grade.name();
+            let px = popup.x as i32 - name.len() as i32 * 5 / 2 + shake_x;
+            let py = popup.y as i32 + shake_y;
+            text!("{}", name; x = px, y = py, font = "small", color = (color & 0xffffff00) | alpha);
+        }
     }
     
     // ========================================================================
@@ -1637,7 +2114,7 @@ impl GameState {
     // ========================================================================
     
     fn draw_powerups(&self, shake_x: i32, shake_y: i32) {
-        for powerup in &self.powerups {
+        for powerup in &self.sim.powerups {
             if !powerup.active { continue; }
             
             let bob_y = powerup.y + (powerup.bob_offset.sin() * 5.0);
@@ -1646,30 +2123,139 @@ impl GameState {
             
             // Glow effect
             let glow_size = 20 + ((self.frame as f32 / 10.0).sin() * 3.0) as u32;
-            let glow_color = if powerup.kind == POWERUP_HEALTH { 0x44ff4422 } else { 0xffff0022 };
-            circ!(x = px, y = py, d = glow_size, color = glow_color);
+            circ!(x = px, y = py, d = glow_size, color = (powerup_color(powerup.kind) & 0xffffff00) | 0x22);
             
-            match powerup.kind {
-                POWERUP_HEALTH => {
-                    // Candy cane
-                    rect!(x = px - 2, y = py - 8, w = 4, h = 16, color = 0xffffffff);
-                    rect!(x = px - 1, y = py - 7, w = 2, h = 3, color = 0xff0000ff);
-                    rect!(x = px - 1, y = py - 1, w = 2, h = 3, color = 0xff0000ff);
-                    rect!(x = px - 1, y = py + 5, w = 2, h = 3, color = 0xff0000ff);
-                    // Hook
-                    rect!(x = px - 6, y = py - 8, w = 6, h = 4, color = 0xffffffff);
-                    rect!(x = px - 7, y = py - 5, w = 4, h = 3, color = 0xff0000ff);
+            // Picked up, it would add one more stack
+            let stacks = (self.sim.powerup_stacks(powerup.kind) + 1).min(powerup.kind.max_stacks());
+            self.draw_powerup_icon(powerup.kind, stacks, px, py);
+        }
+    }
+    
+    /// Pickup art for each power-up, about 16px across, centred on (px, py).
+    /// The multiplier shows the score factor `stacks` of it give.
+    fn draw_powerup_icon(&self, kind: PowerUpKind, stacks: u32, px: i32, py: i32) {
+        match kind {
+            PowerUpKind::Health => {
+                // Candy cane
+                rect!(x = px - 2, y = py - 8, w = 4, h = 16, color = 0xffffffff);
+                rect!(x = px - 1, y = py - 7, w = 2, h = 3, color = 0xff0000ff);
+                rect!(x = px - 1, y = py - 1, w = 2, h = 3, color = 0xff0000ff);
+                rect!(x = px - 1, y = py + 5, w = 2, h = 3, color = 0xff0000ff);
+                // Hook
+                rect!(x = px - 6, y = py - 8, w = 6, h = 4, color = 0xffffffff);
+                rect!(x = px - 7, y = py - 5, w = 4, h = 3, color = 0xff0000ff);
+            }
+            PowerUpKind::Star => {
+                let star_color = if (self.frame / 5).is_multiple_of(2) { COLOR_GOLD } else { COLOR_STAR };
+                // Simple star shape
+                circ!(x = px, y = py, d = 10, color = star_color);
+                rect!(x = px - 1, y = py - 8, w = 3, h = 16, color = star_color);
+                rect!(x = px - 8, y = py - 1, w = 16, h = 3, color = star_color);
+            }
+            PowerUpKind::MultiGift => {
+                // Three little presents
+                for (dx, dy, color) in [(-6, 2, 0xff0000ff), (6, 2, 0x00aa44ff), (0, -5, 0x3366ffff)] {
+                    rect!(x = px + dx - 4, y = py + dy - 4, w = 8, h = 8, color = color);
+                    rect!(x = px + dx - 1, y = py + dy - 4, w = 2, h = 8, color = COLOR_GOLD);
+                }
+            }
+            PowerUpKind::Magnet => {
+                // Horseshoe magnet
+                rect!(x = px - 7, y = py - 7, w = 4, h = 12, color = 0xdd2222ff);
+                rect!(x = px + 3, y = py - 7, w = 4, h = 12, color = 0xdd2222ff);
+                rect!(x = px - 7, y = py + 3, w = 14, h = 4, color = 0xdd2222ff);
+                rect!(x = px - 7, y = py - 8, w = 4, h = 3, color = 0xddddddff);
+                rect!(x = px + 3, y = py - 8, w = 4, h = 3, color = 0xddddddff);
+            }
+            PowerUpKind::SlowMo => {
+                // Hourglass
+                rect!(x = px - 6, y = py - 8, w = 12, h = 2, color = 0x8b4513ff);
+                rect!(x = px - 6, y = py + 6, w = 12, h = 2, color = 0x8b4513ff);
+                for row in 0..6 {
+                    let w = 10 - row * 2;
+                    rect!(x = px - w / 2, y = py - 6 + row, w = w.max(1) as u32, h = 1, color = 0x88ccffff);
+                    rect!(x = px - w / 2, y = py + 5 - row, w = w.max(1) as u32, h = 1, color = 0x88ccffff);
+                }
+            }
+            PowerUpKind::Multiplier => {
+                circ!(x = px, y = py, d = 16, color = COLOR_GOLD);
+                text!("x{}", 1 + stacks; x = px - 5, y = py - 3, font = "small", color = 0x442200ff);
+            }
+            PowerUpKind::Shield => {
+                rect!(x = px - 6, y = py - 7, w = 12, h = 8, color = 0x66ccffff);
+                for row in 0..6 {
+                    let w = 12 - row * 2;
+                    rect!(x = px - w / 2, y = py + 1 + row, w = w as u32, h = 1, color = 0x66ccffff);
                 }
-                POWERUP_INVINCIBLE => {
-                    // Star
-                    let star_color = if (self.frame / 5) % 2 == 0 { COLOR_GOLD } else { COLOR_STAR };
-                    // Simple star shape
-                    circ!(x = px, y = py, d = 10, color = star_color);
-                    rect!(x = px - 1, y = py - 8, w = 3, h = 16, color = star_color);
-                    rect!(x = px - 8, y = py - 1, w = 16, h = 3, color = star_color);
+                rect!(x = px - 1, y = py - 5, w = 2, h = 9, color = 0xffffffff);
+            }
+            PowerUpKind::ComboExtend => {
+                // Clock face
+                circ!(x = px, y = py, d = 16, color = 0xff00ffff);
+                circ!(x = px, y = py, d = 12, color = 0xffffffff);
+                rect!(x = px, y = py - 5, w = 1, h = 5, color = 0x000000ff);
+                rect!(x = px, y = py, w = 4, h = 1, color = 0x000000ff);
+            }
+        }
+    }
+    
+    /// Running power-ups along the top left, each with its remaining time
+    fn draw_active_powerups(&self, y: i32) {
+        for (i, active) in self.sim.active_powerups.iter().enumerate() {
+            let x = 14 + i as i32 * 24;
+            self.draw_powerup_icon(active.kind, active.stacks, x, y);
+            
+            if let Some(left) = (20 * active.timer).checked_div(active.kind.duration()) {
+                // Blink for the last two seconds
+                let fading = active.timer < 120 && (self.frame / 6).is_multiple_of(2);
+                let w = left.max(1);
+                rect!(x = x - 10, y = y + 11, w = 20, h = 2, color = 0x333333ff);
+                if !fading {
+                    rect!(x = x - 10, y = y + 11, w = w, h = 2, color = powerup_color(active.kind));
                 }
-                _ => {}
             }
+            // The multiplier's icon already shows its stacked factor
+            if active.stacks > 1 && active.kind != PowerUpKind::Multiplier {
+                text!("x{}", active.stacks; x = x + 4, y = y + 3, font = "small", color = 0xffffffff);
+            }
+        }
+    }
+    
+    /// Versus game over: the winner and both players' race side by side
+    fn draw_versus_results(&self) {
+        match self.sim.versus_winner() {
+            Some(i) => text!("P{} WINS!", i + 1; x = 72, y = 35, font = "large", color = PLAYER_COLORS[i]),
+            None => text!("DRAW", x = 72, y = 35, font = "large", color = COLOR_GOLD),
+        }
+        
+        let labels = ["Deliveries", "Accuracy", "Max Combo", "Score"];
+        for (row, label) in labels.iter().enumerate() {
+            text!("{}", label; x = 80, y = 72 + row as i32 * 12, font = "small", color = 0xaaaaaaff);
+        }
+        for (i, player) in self.sim.players.iter().enumerate() {
+            let x = 160 + i as i32 * 80;
+            let status = if player.flying() { "" } else { " (down)" };
+            text!("P{}{}", i + 1, status; x = x, y = 58, font = "small", color = PLAYER_COLORS[i]);
+            let values = [
+                format!("{}/{}", player.deliveries, VERSUS_TARGET),
+                format!("{}%", player.accuracy()),
+                player.max_combo.to_string(),
+                player.score.to_string(),
+            ];
+            for (row, value) in values.iter().enumerate() {
+                text!("{}", value; x = x, y = 72 + row as i32 * 12, font = "small", color = 0xffffffff);
+            }
+        }
+    }
+    
+    /// A night's rating as three stars, `stars` of them lit
+    fn draw_stars(&self, x: i32, y: i32, stars: u8) {
+        for i in 0..3u8 {
+            let sx = x + i as i32 * 14;
+            let color = if i < stars { COLOR_GOLD } else { 0x444444ff };
+            circ!(x = sx - 3, y = y - 3, d = 7, color = color);
+            rect!(x = sx - 1, y = y - 6, w = 3, h = 13, color = color);
+            rect!(x = sx - 6, y = y - 1, w = 13, h = 3, color = color);
         }
     }
     
@@ -1678,8 +2264,8 @@ impl GameState {
     // ========================================================================
     
     fn draw_combo(&self) {
-        if self.combo_count >= 2 {
-            let combo_color = match self.combo_count {
+        if self.sim.combo_count >= 2 {
+            let combo_color = match self.sim.combo_count {
                 2..=3 => 0x00ff00ff,
                 4..=5 => 0xffff00ff,
                 _ => 0xff00ffff,
@@ -1687,7 +2273,7 @@ impl GameState {
             
             // Pulsing effect
             let pulse = ((self.frame as f32 / 8.0).sin() * 2.0) as i32;
-            text!("COMBO x{}", self.combo_count; x = 280 + pulse, y = 50, font = "medium", color = combo_color);
+            text!("COMBO x{}", self.sim.combo_count; x = 280 + pulse, y = 50, font = "medium", color = combo_color);
         }
     }
     
@@ -1697,7 +2283,7 @@ impl GameState {
     
     fn draw_fade(&self) {
         if self.fade_alpha > 0 {
-            let color = 0x000000ff & 0xffffff00 | self.fade_alpha;
+            let color = self.fade_alpha;
             rect!(x = 0, y = 0, w = SCREEN_W as u32, h = SCREEN_H as u32, color = color);
         }
     }
@@ -1707,47 +2293,116 @@ impl GameState {
     // ========================================================================
     
     pub fn update(&mut self) {
-        self.frame += 1;
+        self.sound.begin_frame();
         
-        // Handle pause toggle
-        let kb = keyboard::get();
-        if kb.escape().just_pressed() && (self.mode == MODE_DELIVERING || self.mode == MODE_KRAMPUS || self.mode == MODE_PAUSED) {
-            self.toggle_pause();
+        if !self.handle_menu_input() {
+            // Exit game with ESC on the title screen (note: in browser this may just unfocus)
+            if self.sim.mode == GameMode::Title && keyboard::get().escape().just_pressed() {
+                std::process::exit(0);
+            }
+            
+            self.latch_input();
         }
         
-        // If paused, just draw pause screen and return
-        if self.mode == MODE_PAUSED {
-            self.draw_pause_screen();
-            return;
+        // Run as many fixed ticks as real time calls for, whatever the display rate
+        let now = time::now();
+        if self.last_update_ms == 0 || now < self.last_update_ms {
+            self.tick_accumulator = 1000;  // First frame (or clock went backwards): one tick
+        } else {
+            self.tick_accumulator += (now - self.last_update_ms) * TICK_RATE;
         }
+        self.last_update_ms = now;
         
-        // Decrease effects
-        if self.screen_flash > 0 { self.screen_flash -= 1; }
-        if self.screen_shake > 0 { self.screen_shake -= 1; }
-        if self.invincible_timer > 0 { self.invincible_timer -= 1; }
-        
-        // Update particles
-        self.update_particles();
+        let mut ticks = 0;
+        while self.tick_accumulator >= 1000 && ticks < MAX_TICKS_PER_FRAME {
+            self.tick();
+            self.tick_accumulator -= 1000;
+            ticks += 1;
+        }
+        if ticks == MAX_TICKS_PER_FRAME {
+            self.tick_accumulator %= 1000;
+        }
+        self.render_alpha = self.tick_accumulator as f32 / 1000.0;
         
-        // Update fade transitions
-        self.update_fade();
+        self.draw();
+    }
+    
+    /// One fixed step of gameplay and effects
+    fn tick(&mut self) {
+        self.frame += 1;
         
-        // Update combo timer
-        self.update_combo();
+        // Advance gameplay one tick and react to what happened
+        let inputs = self.next_input();
+        let inputs = &inputs[..(self.sim.player_count as usize).clamp(1, MAX_PLAYERS)];
+        if self.playback.is_none() && self.sim.in_run() {
+            self.recording.record(inputs);
+            // The ghost moves on the same ticks as the live run, but not while it's paused
+            if self.sim.mode != GameMode::Paused
+                && let Some(ghost) = &mut self.ghost
+            {
+                ghost.step();
+            }
+        }
+        // Every fresh run gets its own layout (the daily challenge seeds itself),
+        // except a ghost race, which flies the ghost's run
+        if inputs.iter().any(|i| i.start) && self.playback.is_none() && matches!(self.sim.mode, GameMode::Title | GameMode::GameOver) {
+            self.sim.rng = Rng::new(time::now() as u32 ^ self.fx_rng.next_u32());
+            self.apply_players();
+            if let Some(replay) = self.ghost_replay() {
+                self.sim.rng = Rng::new(replay.seed);
+                self.sim.run_mode = replay.mode;
+                self.sim.player_count = replay.players;
+                self.sim.shared_lives = replay.shared_lives;
+            }
+        }
+        let mut events = self.sim.step(inputs);
         
-        // Update snowflakes always
-        self.update_snowflakes();
+        // A replay that runs out of input before game over has diverged
+        if let Some(replay) = &self.playback
+            && self.playback_tick as usize >= replay.frames.len()
+        {
+            events.extend(self.sim.end_run());
+        }
         
-        // Keep music playing (auto-loop)
-        self.update_music();
+        for event in events {
+            self.handle_event(event);
+        }
         
+        // Effects stay frozen while paused
+        if self.sim.mode != GameMode::Paused {
+            // Decrease effects
+            if self.screen_flash > 0 { self.screen_flash -= 1; }
+            if self.screen_shake > 0 { self.screen_shake -= 1; }
+            if self.krampus_hit_flash > 0 { self.krampus_hit_flash -= 1; }
+            self.attack_notice = self.attack_notice.and_then(|(from, naughty, t)| (t > 1).then_some((from, naughty, t - 1)));
+            
+            // Update particles
+            self.update_particles();
+            
+            // Update fade transitions
+            self.update_fade();
+            
+            // Update snowflakes
+            self.update_snowflakes();
+            
+            // Music fades, ducking and looping
+            self.sound.update();
+            
+            // Tutorial overlay countdown
+            if self.tutorial_timer > 0 && matches!(self.sim.mode, GameMode::Delivering | GameMode::KrampusWarning) {
+                self.tutorial_timer -= 1;
+            }
+        }
+    }
+    
+    fn draw(&mut self) {
         let (shake_x, shake_y) = self.get_shake();
         
-        match self.mode {
+        match self.sim.mode {
             // ================================================================
             // TITLE SCREEN
             // ================================================================
-            MODE_TITLE => {
+            GameMode::Title => {
                 self.draw_background(0, 0);
                 self.draw_snowflakes();
                 
@@ -1768,19 +2423,38 @@ impl GameState {
                 text!("SANTA", x = 112, y = title_y + 2, font = "large", color = 0x00000088);
                 text!("DELIVERY", x = 177, y = title_y + 2, font = "large", color = 0x00000088);
                 // Main text with color pulse
-                let santa_red = if (self.frame / 20) % 2 == 0 { 0xff0000ff } else { 0xff2222ff };
+                let santa_red = if (self.frame / 20).is_multiple_of(2) { 0xff0000ff } else { 0xff2222ff };
                 text!("SANTA", x = 110, y = title_y, font = "large", color = santa_red);
                 text!("DELIVERY", x = 175, y = title_y, font = "large", color = COLOR_GOLD);
                 
+                // Run mode, picked with left/right (and the night with up/down)
+                text!("< {} >", self.sim.run_mode.name(); x = 164, y = 90, font = "medium", color = 0xaaccffff);
+                if let RunMode::Campaign(n) = self.sim.run_mode
+                    && let Some(night) = self.sim.night()
+                {
+                    let record = self.save.night_record(n);
+                    text!("Dec {}: {}", n, night.name; x = 120, y = 104, font = "small", color = COLOR_GOLD);
+                    self.draw_stars(250, 107, record.stars);
+                    if record.best_score > 0 {
+                        text!("Best {}", record.best_score; x = 296, y = 104, font = "small", color = 0xaaaaaaff);
+                    }
+                }
+                if let RunMode::Daily(day) = self.sim.run_mode {
+                    text!("{}", daily::date_string(day); x = 150, y = 104, font = "small", color = COLOR_GOLD);
+                    if let Some(best) = self.save.daily_best(day) {
+                        text!("Best {}", best.score; x = 210, y = 104, font = "small", color = 0xaaaaaaff);
+                    }
+                }
+                if self.sim.run_mode == RunMode::Versus {
+                    text!("First to {} gifts - gamepads 1 and 2", VERSUS_TARGET; x = 110, y = 104, font = "small", color = COLOR_GOLD);
+                }
+                
                 // Sleigh preview (centered)
                 let preview_y = 120.0 + (self.frame as f32 / 20.0).sin() * 8.0;
-                let old_y = self.player_y;
-                self.player_y = preview_y;
-                self.draw_sleigh(100, 0); // Offset to center
-                self.player_y = old_y;
+                self.draw_sleigh(&Player::new(PLAYER_X), preview_y, 100, 0); // Offset to center
                 
                 // Instructions
-                if (self.frame / 30) % 2 == 0 {
+                if (self.frame / 30).is_multiple_of(2) {
                     text!("Press ENTER or START to Fly!", x = 100, y = 155, font = "medium", color = 0xffffffff);
                 }
                 
@@ -1788,48 +2462,35 @@ impl GameState {
                 self.draw_controls_hint();
                 
                 // High score
-                if self.high_score > 0 {
-                    text!("Best: {}", self.high_score; x = 160, y = 180, font = "small", color = COLOR_GOLD);
+                if self.save.high_score > 0 {
+                    text!("Best: {}", self.save.high_score; x = 160, y = 180, font = "small", color = COLOR_GOLD);
                 }
                 
-                // Exit hint
-                text!("ESC to Exit", x = 320, y = 200, font = "small", color = 0x666666ff);
-                
-                // Input handling
-                let gp = gamepad::get(0);
-                let kb = keyboard::get();
-                
-                if gp.start.just_pressed() || gp.a.just_pressed() || kb.enter().just_pressed() {
-                    self.start_game();
+                // Ghost to race, picked with G (or Y)
+                match self.ghost_replay() {
+                    Some(replay) => text!("[G] Ghost: {} ({})", self.ghost_source.name(), replay.summary.score; x = 8, y = 170, font = "small", color = 0xccddffff),
+                    None if self.ghost_source == GhostSource::Best => {
+                        text!("[G] Ghost: Personal best (none for this mode yet)", x = 8, y = 170, font = "small", color = 0x888888ff)
+                    }
+                    None => text!("[G] Ghost: Off", x = 8, y = 170, font = "small", color = 0x888888ff),
                 }
                 
-                // Exit game with ESC (note: in browser this may just unfocus)
-                // Exit game with ESC
-                if kb.escape().just_pressed() {
-                    std::process::exit(0);
-                }
+                text!("[I] Import ghost", x = 8, y = 180, font = "small", color = 0x888888ff);
+                text!("[L] Top 10", x = 8, y = 190, font = "small", color = 0x888888ff);
+                text!("[O] Options", x = 8, y = 200, font = "small", color = 0x888888ff);
                 
-                if gp.start.just_pressed() || kb.enter().just_pressed() {
-                    Self::play_sfx("start"); // Play sound immediately
-                    self.start_game();
+                // Exit hint
+                text!("ESC to Exit", x = 320, y = 200, font = "small", color = 0x666666ff);
+                
+                if let Some(import) = &self.ghost_import {
+                    self.draw_ghost_import(import);
                 }
             }
             
             // ================================================================
             // DELIVERING MODE
             // ================================================================
-            MODE_DELIVERING => {
-                // Update game logic
-                self.update_scroll();
-                self.move_player();
-                self.update_chimneys();
-                self.drop_gift();
-                self.update_gifts();
-                self.check_krampus_trigger();
-                self.update_krampus_warning();
-                self.update_powerups();  // NEW: Power-ups
-                
-                // Draw
+            GameMode::Delivering | GameMode::KrampusWarning => {
                 self.draw_background(shake_x, shake_y);
                 self.draw_snowflakes();
                 
@@ -1837,13 +2498,25 @@ impl GameState {
                 self.draw_powerups(shake_x, shake_y);
                 
                 // Draw chimneys
-                for chimney in &self.chimneys {
+                for chimney in &self.sim.chimneys {
                     self.draw_chimney(chimney, shake_x, shake_y);
                 }
                 
+                // Enemies and their shots
+                for enemy in &self.sim.enemies {
+                    self.draw_enemy(enemy, shake_x, shake_y);
+                }
+                for proj in &self.sim.projectiles {
+                    self.draw_projectile(proj, shake_x, shake_y);
+                }
+                
+                if self.save.settings.aim_guide {
+                    self.draw_aim_guide(shake_x, shake_y);
+                }
+                
                 // Draw falling gifts
-                for gift in &self.gifts {
-                    if gift.active {
+                for gift in &self.sim.gifts {
+                    if gift.outcome.in_flight() {
                         self.draw_falling_gift(gift, shake_x, shake_y);
                     }
                 }
@@ -1851,13 +2524,8 @@ impl GameState {
                 // Draw particles (above gifts, below sleigh)
                 self.draw_particles(shake_x, shake_y);
                 
-                // Draw sleigh (with star power glow if active)
-                if self.star_power_timer > 0 {
-                    // Draw aura around sleigh
-                    let glow_alpha = ((self.frame as f32 / 5.0).sin() * 50.0 + 150.0) as u32;
-                    circ!(x = PLAYER_X as i32 + shake_x, y = self.player_y as i32 + shake_y, d = 50, color = 0xffff0000 | glow_alpha);
-                }
-                self.draw_sleigh(shake_x, shake_y);
+                self.draw_ghost(shake_x, shake_y);
+                self.draw_player(shake_x, shake_y);
                 
                 // UI
                 self.draw_ui(shake_x, shake_y);
@@ -1867,7 +2535,6 @@ impl GameState {
                 
                 // Tutorial overlay (first game only)
                 if self.tutorial_timer > 0 {
-                    self.tutorial_timer -= 1;
                     self.draw_tutorial();
                 }
             }
@@ -1875,91 +2542,179 @@ impl GameState {
             // ================================================================
             // KRAMPUS ATTACK MODE
             // ================================================================
-            MODE_KRAMPUS => {
-                // Update
-                self.update_scroll();
-                self.move_player();
-                self.update_krampus();
-                self.update_projectiles();
-                
+            GameMode::Krampus => {
                 // Darker background during attack
                 clear(0x0a0a14ff);
                 
                 // Draw stars dimmed
                 for i in 0..10 {
-                    let star_x = ((i * 37 + 10) as f32 - (self.scroll_x * 0.1) % SCREEN_W) as i32;
-                    let star_y = (i * 7 % 50 + 5) as i32;
+                    let star_x = ((i * 37 + 10) as f32 - (self.sim.scroll_x * 0.1) % SCREEN_W) as i32;
+                    let star_y = i * 7 % 50 + 5;
                     circ!(x = star_x + shake_x, y = star_y + shake_y, d = 2, color = 0xffffff33);
                 }
                 
-                // Ground (darker)
                 // Ground (darker for Krampus mode, full width)
                 let ground_y = (SCREEN_H * 0.78) as i32;
                 rect!(x = shake_x, y = ground_y + shake_y, w = SCREEN_W as u32, h = 50, color = 0x404050ff);
                 
                 self.draw_snowflakes();
                 
-                // Draw sleigh
-                self.draw_sleigh(shake_x, shake_y);
+                self.draw_ghost(shake_x, shake_y);
+                self.draw_player(shake_x, shake_y);
                 
-                // Draw Krampus
-                self.draw_krampus(shake_x, shake_y);
+                // Krampus (rooftop enemies wait with their houses off screen)
+                for enemy in &self.sim.enemies {
+                    if !enemy.kind.on_rooftop() {
+                        self.draw_enemy(enemy, shake_x, shake_y);
+                    }
+                }
                 
                 // Draw projectiles
-                for proj in &self.projectiles {
+                for proj in &self.sim.projectiles {
                     if proj.active {
                         self.draw_projectile(proj, shake_x, shake_y);
                     }
                 }
                 
+                // Snowballs thrown back
+                for ball in &self.sim.snowballs {
+                    self.draw_snowball(ball, shake_x, shake_y);
+                }
+                
+                self.draw_particles(shake_x, shake_y);
+                
                 // UI
                 self.draw_ui(shake_x, shake_y);
+                self.draw_krampus_health();
                 
                 // Survive timer
-                let seconds_left = self.krampus_duration / 60;
+                let seconds_left = self.sim.krampus_duration / 60;
                 text!("Survive: {}s", seconds_left; x = 100, y = 60, font = "medium", color = 0xff6600ff);
+                if self.sim.krampus_duration + 150 > self.sim.config().krampus_duration {
+                    text!("...or hit him with snowballs! [ENTER]", x = 100, y = 76, font = "small", color = COLOR_SNOW);
+                }
             }
             
             // ================================================================
             // GAME OVER
             // ================================================================
-            MODE_GAMEOVER => {
+            GameMode::GameOver => {
                 clear(0x0a0a0aff);
                 
-                text!("GAME OVER", x = 72, y = 35, font = "large", color = 0xff0000ff);
+                if self.sim.run_mode == RunMode::Versus {
+                    self.draw_versus_results();
+                } else {
+                    match self.night_result {
+                        Some(result) if result.stars > 0 => {
+                            text!("NIGHT CLEARED", x = 72, y = 35, font = "large", color = COLOR_GOLD);
+                            self.draw_stars(250, 40, result.stars);
+                        }
+                        Some(_) => text!("NIGHT FAILED", x = 72, y = 35, font = "large", color = 0xff0000ff),
+                        None => text!("GAME OVER", x = 72, y = 35, font = "large", color = 0xff0000ff),
+                    }
+                
+                    text!("Score: {}", self.sim.score; x = 92, y = 55, font = "medium", color = 0xffffffff);
+                    text!("Deliveries: {}", self.sim.deliveries; x = 80, y = 73, font = "small", color = 0x00ff00ff);
+                    text!("Max Combo: {}", self.sim.max_combo; x = 80, y = 87, font = "small", color = 0xff00ffff);
+                    text!("Level: {}", self.sim.level; x = 100, y = 101, font = "small", color = 0xaaaaaaff);
                 
-                text!("Score: {}", self.score; x = 92, y = 55, font = "medium", color = 0xffffffff);
-                text!("Deliveries: {}", self.deliveries; x = 80, y = 73, font = "small", color = 0x00ff00ff);
-                text!("Max Combo: {}", self.max_combo; x = 80, y = 87, font = "small", color = 0xff00ffff);
-                text!("Level: {}", self.level; x = 100, y = 101, font = "small", color = 0xaaaaaaff);
+                    // Aim: deliveries per gift dropped, and how they were graded
+                    let [perfect, great, good] = self.sim.grade_counts;
+                    text!("Accuracy: {}%", self.sim.accuracy(); x = 200, y = 73, font = "small", color = 0x66ccffff);
+                    text!("Perfect {}  Great {}  Good {}", perfect, great, good; x = 200, y = 87, font = "small", color = 0xaaaaaaff);
+                    if self.sim.run_mode == RunMode::Endless {
+                        text!("Distance: {}m", self.sim.distance as u32 / 10; x = 200, y = 101, font = "small", color = 0xaaccffff);
+                    }
+                    if let RunMode::Daily(day) = self.sim.run_mode {
+                        text!("Daily {}", daily::date_string(day); x = 200, y = 59, font = "small", color = COLOR_GOLD);
+                    }
+                    if let Some(result) = self.night_result
+                        && let Some(night) = self.sim.campaign.get(result.night)
+                    {
+                        text!("Dec {}: {}", result.night, night.name; x = 200, y = 59, font = "small", color = COLOR_GOLD);
+                        text!("Target: {}/{} gifts", self.sim.deliveries.min(night.target), night.target; x = 200, y = 101, font = "small", color = 0x00ff00ff);
+                    }
                 
-                if self.score > self.high_score && self.score > 0 {
-                    if (self.frame / 15) % 2 == 0 {
-                        text!("NEW HIGH SCORE!", x = 60, y = 118, font = "medium", color = COLOR_GOLD);
+                    // Co-op: who earned what, and what only the team earned (combos, Krampus)
+                    if self.sim.players.len() > 1 {
+                        for (i, player) in self.sim.players.iter().enumerate() {
+                            let y = 135 + i as i32 * 14;
+                            text!("P{}: {} pts, {} gifts", i + 1, player.score, player.deliveries; x = 220, y = y, font = "small", color = PLAYER_COLORS[i]);
+                        }
+                        let own: u32 = self.sim.players.iter().map(|p| p.score).sum();
+                        text!("Team bonus: {}", self.sim.score.saturating_sub(own); x = 220, y = 163, font = "small", color = 0xaaaaaaff);
                     }
-                }
                 
-                if (self.frame / 25) % 2 == 0 {
-                    text!("Press START to Retry", x = 56, y = 135, font = "small", color = 0x888888ff);
+                    // Ghost race result against the ghost's final score
+                    if let Some(ghost) = &self.ghost {
+                        let delta = self.sim.score as i64 - ghost.replay.summary.score as i64;
+                        match delta {
+                            0 => text!("Tied with the ghost", x = 200, y = 115, font = "small", color = 0xccddffff),
+                            d if d > 0 => text!("Beat the ghost by {}", d; x = 200, y = 115, font = "small", color = 0x66ff66ff),
+                            d => text!("Ghost won by {}", -d; x = 200, y = 115, font = "small", color = 0xff6666ff),
+                        }
+                    }
+                    if self.new_ghost {
+                        text!("Saved as your ghost", x = 200, y = 125, font = "small", color = 0xccddffff);
+                    }
                 }
                 
-                let gp = gamepad::get(0);
-                let kb = keyboard::get();
-                if gp.start.just_pressed() || gp.a.just_pressed() || kb.enter().just_pressed() {
-                    self.reset_game();
+                if let Some(entry) = &self.initials_entry {
+                    self.draw_initials_entry(entry);
+                } else {
+                    match self.replay_verified {
+                        Some(true) => text!("REPLAY VERIFIED", x = 60, y = 118, font = "medium", color = 0x00ff00ff),
+                        Some(false) => text!("REPLAY MISMATCH", x = 60, y = 118, font = "medium", color = 0xff0000ff),
+                        None => {
+                            if self.new_high_score && (self.frame / 15).is_multiple_of(2) {
+                                text!("NEW HIGH SCORE!", x = 60, y = 118, font = "medium", color = COLOR_GOLD);
+                            }
+                            if self.night_result.is_some_and(|r| r.new_best) && (self.frame / 15).is_multiple_of(2) {
+                                text!("NEW NIGHT BEST!", x = 60, y = 118, font = "medium", color = COLOR_GOLD);
+                            }
+                            if self.new_daily_best && !self.new_high_score && (self.frame / 15).is_multiple_of(2) {
+                                text!("NEW DAILY BEST!", x = 60, y = 118, font = "medium", color = COLOR_GOLD);
+                            }
+                        }
+                    }
+                    
+                    if (self.frame / 25).is_multiple_of(2) {
+                        match self.sim.run_mode {
+                            RunMode::Campaign(night) => text!("Press START to fly Dec {}", night; x = 56, y = 135, font = "small", color = 0x888888ff),
+                            _ => text!("Press START to Retry", x = 56, y = 135, font = "small", color = 0x888888ff),
+                        }
+                    }
+                    
+                    if self.last_replay.is_some() {
+                        text!("[R] Watch replay", x = 56, y = 149, font = "small", color = 0x888888ff);
+                    }
+                    text!("[L] Leaderboard", x = 56, y = 163, font = "small", color = 0x888888ff);
+                    if let Some(share) = &self.daily_share {
+                        text!("{}", share; x = 56, y = 183, font = "small", color = 0xddddddff);
+                    }
                 }
             }
             
-            _ => {}
+            // ================================================================
+            // PAUSED (overlay only)
+            // ================================================================
+            GameMode::Paused => {
+                self.draw_pause_screen();
+                self.draw_menu();
+                return;
+            }
         }
         
         // Screen flash overlay
         if self.screen_flash > 0 {
-            let alpha = ((self.screen_flash as f32 / 15.0) * 180.0) as u32;
+            let alpha = ((self.screen_flash as f32 / 15.0) * 180.0 * self.save.settings.flash_intensity) as u32;
             let flash = (self.flash_color & 0xffffff00) | alpha;
             rect!(x = 0, y = 0, w = SCREEN_W as u32, h = SCREEN_H as u32, color = flash);
         }
         
+        // Screens drawn over everything else
+        self.draw_menu();
+        
         // Fade transition overlay
         self.draw_fade();
     }
@@ -0,0 +1,295 @@
+// BULLET PATTERNS
+//
+// Krampus's attacks are described in patterns.toml (embedded at build time) as
+// emitters with counts, angles, delays and bullet behaviour, so new patterns
+// need no new code. Bad values are clamped or dropped with a warning, in the
+// same way as levels.toml.
+
+use crate::tuning::clamp_warn;
+use turbo::serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const PATTERNS_TOML: &str = include_str!("../patterns.toml");
+
+/// Where an emitter points before its angle offset
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Aim {
+    #[default]
+    Player,  // Towards the sleigh
+    Left,    // Straight across the screen
+}
+
+/// A bullet that bursts into several more after a while
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub struct Split {
+    pub after: u32,   // Ticks after firing
+    pub count: u32,
+    pub spread: f32,  // Radians between the new bullets
+    pub speed: f32,   // Multiple of the level's projectile speed
+}
+
+/// How each bullet moves once fired
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Default)]
+pub struct Motion {
+    pub accel: f32,        // Speed change per tick
+    pub turn: f32,         // Radians per tick
+    pub homing: f32,       // Max radians per tick towards the sleigh
+    pub wobble: f32,       // Sideways amplitude in pixels
+    pub wobble_freq: f32,  // Radians per tick
+    pub split: Option<Split>,
+}
+
+/// One source of bullets within a pattern
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub struct Emitter {
+    pub aim: Aim,
+    pub angle: f32,     // Radians added to the aim (positive is clockwise)
+    pub jitter: f32,    // Random +/- radians per volley
+    pub count: u32,
+    pub spread: f32,    // Radians between bullets in one shot
+    pub delay: u32,     // Ticks before the first shot
+    pub repeat: u32,    // Shots fired
+    pub interval: u32,  // Ticks between shots
+    pub rotate: f32,    // Radians added per repeat
+    pub speed: f32,     // Multiple of the level's projectile speed
+    pub motion: Motion,
+}
+
+#[turbo::serialize]
+pub struct Pattern {
+    pub name: String,
+    pub emitters: Vec<Emitter>,
+}
+
+/// Every pattern levels can refer to
+#[turbo::serialize]
+pub struct PatternLibrary {
+    pub patterns: Vec<Pattern>,
+}
+
+// Raw file shape
+#[derive(Deserialize, Default)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawLibrary {
+    pattern: BTreeMap<String, RawPattern>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawPattern {
+    emitter: Vec<RawEmitter>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawEmitter {
+    aim: Aim,
+    angle: f32,
+    jitter: f32,
+    count: u32,
+    spread: f32,
+    delay: u32,
+    repeat: u32,
+    interval: u32,
+    rotate: f32,
+    speed: f32,
+    accel: f32,
+    turn: f32,
+    homing: f32,
+    wobble: f32,
+    wobble_freq: f32,
+    split: Option<RawSplit>,
+}
+
+impl Default for RawEmitter {
+    fn default() -> Self {
+        Self {
+            aim: Aim::Player,
+            angle: 0.0,
+            jitter: 0.0,
+            count: 1,
+            spread: 0.0,
+            delay: 0,
+            repeat: 1,
+            interval: 0,
+            rotate: 0.0,
+            speed: 1.0,
+            accel: 0.0,
+            turn: 0.0,
+            homing: 0.0,
+            wobble: 0.0,
+            wobble_freq: 0.0,
+            split: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawSplit {
+    after: u32,
+    count: u32,
+    spread: f32,
+    speed: f32,
+}
+
+impl Default for RawSplit {
+    fn default() -> Self {
+        Self { after: 30, count: 3, spread: 30.0, speed: 1.0 }
+    }
+}
+
+impl PatternLibrary {
+    /// The built-in patterns, or the fallback if they fail to parse
+    pub fn embedded() -> Self {
+        Self::parse(PATTERNS_TOML).0
+    }
+
+    /// A library with just the plain aimed shot, used when nothing else loads
+    pub fn fallback() -> Self {
+        Self {
+            patterns: vec![Pattern {
+                name: "aimed".to_string(),
+                emitters: vec![RawEmitter::default().build(&mut vec![], "")],
+            }],
+        }
+    }
+
+    /// Parse and validate patterns, returning any problems found. Never fails.
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut warnings = vec![];
+        let raw: RawLibrary = match toml::from_str(text) {
+            Ok(raw) => raw,
+            Err(err) => {
+                warnings.push(format!("patterns.toml: {err}; using a plain aimed shot"));
+                return (Self::fallback(), warnings);
+            }
+        };
+
+        let mut patterns = vec![];
+        for (name, raw) in raw.pattern {
+            if raw.emitter.is_empty() {
+                warnings.push(format!("patterns.toml: {name} has no emitters, skipping it"));
+                continue;
+            }
+            let emitters = raw
+                .emitter
+                .into_iter()
+                .enumerate()
+                .map(|(i, e)| e.build(&mut warnings, &format!("{name} emitter {}", i + 1)))
+                .collect();
+            patterns.push(Pattern { name, emitters });
+        }
+
+        if patterns.is_empty() {
+            warnings.push("patterns.toml: no patterns; using a plain aimed shot".to_string());
+            return (Self::fallback(), warnings);
+        }
+        (Self { patterns }, warnings)
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.patterns.iter().position(|p| p.name == name)
+    }
+}
+
+impl RawEmitter {
+    fn build(self, w: &mut Vec<String>, place: &str) -> Emitter {
+        let split = self.split.map(|s| Split {
+            after: clamp_warn(w, "patterns.toml", place, "split.after", s.after, 1, 600),
+            count: clamp_warn(w, "patterns.toml", place, "split.count", s.count, 1, 24),
+            spread: s.spread.to_radians(),
+            speed: clamp_warn(w, "patterns.toml", place, "split.speed", s.speed, 0.1, 4.0),
+        });
+        Emitter {
+            aim: self.aim,
+            angle: self.angle.to_radians(),
+            jitter: clamp_warn(w, "patterns.toml", place, "jitter", self.jitter, 0.0, 180.0).to_radians(),
+            count: clamp_warn(w, "patterns.toml", place, "count", self.count, 1, 36),
+            spread: self.spread.to_radians(),
+            delay: clamp_warn(w, "patterns.toml", place, "delay", self.delay, 0, 600),
+            repeat: clamp_warn(w, "patterns.toml", place, "repeat", self.repeat, 1, 60),
+            interval: clamp_warn(w, "patterns.toml", place, "interval", self.interval, 0, 120),
+            rotate: self.rotate.to_radians(),
+            speed: clamp_warn(w, "patterns.toml", place, "speed", self.speed, 0.1, 4.0),
+            motion: Motion {
+                accel: clamp_warn(w, "patterns.toml", place, "accel", self.accel, -0.5, 0.5),
+                turn: clamp_warn(w, "patterns.toml", place, "turn", self.turn, -20.0, 20.0).to_radians(),
+                homing: clamp_warn(w, "patterns.toml", place, "homing", self.homing, 0.0, 20.0).to_radians(),
+                wobble: clamp_warn(w, "patterns.toml", place, "wobble", self.wobble, 0.0, 60.0),
+                wobble_freq: clamp_warn(w, "patterns.toml", place, "wobble_freq", self.wobble_freq, 0.0, 2.0),
+                split,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_embedded_patterns_parse_cleanly() {
+        let (library, warnings) = PatternLibrary::parse(PATTERNS_TOML);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(library.find("aimed").is_some());
+    }
+
+    #[test]
+    fn emitter_values_are_clamped_into_range() {
+        let (library, warnings) = PatternLibrary::parse(r#"
+            [[pattern.burst.emitter]]
+            count = 100
+            speed = 9.0
+            jitter = -5.0
+            split = { after = 0, count = 50, speed = 0.0 }
+        "#);
+        let emitter = library.patterns[0].emitters[0];
+        assert_eq!(emitter.count, 36);
+        assert_eq!(emitter.speed, 4.0);
+        assert_eq!(emitter.jitter, 0.0);
+        let split = emitter.motion.split.expect("split was kept");
+        assert_eq!((split.after, split.count, split.speed), (1, 24, 0.1));
+        assert_eq!(warnings, [
+            "patterns.toml: burst emitter 1: split.after = 0 is out of range, using 1",
+            "patterns.toml: burst emitter 1: split.count = 50 is out of range, using 24",
+            "patterns.toml: burst emitter 1: split.speed = 0.0 is out of range, using 0.1",
+            "patterns.toml: burst emitter 1: jitter = -5.0 is out of range, using 0.0",
+            "patterns.toml: burst emitter 1: count = 100 is out of range, using 36",
+            "patterns.toml: burst emitter 1: speed = 9.0 is out of range, using 4.0",
+        ]);
+    }
+
+    #[test]
+    fn values_in_range_and_defaults_pass_untouched() {
+        let (library, warnings) = PatternLibrary::parse(r#"
+            [[pattern.ring.emitter]]
+            aim = "left"
+            count = 12
+            spread = 30.0
+
+            [[pattern.ring.emitter]]
+        "#);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        let emitters = &library.patterns[0].emitters;
+        assert_eq!(emitters[0].aim, Aim::Left);
+        assert_eq!(emitters[0].count, 12);
+        assert_eq!(emitters[0].spread, 30f32.to_radians());
+        assert_eq!((emitters[1].count, emitters[1].repeat, emitters[1].speed), (1, 1, 1.0));
+    }
+
+    #[test]
+    fn unusable_files_fall_back_to_an_aimed_shot() {
+        for text in ["[pattern.x", "[pattern.empty]", "", "[[pattern.x.emitter]]\nlaser = true"] {
+            let (library, warnings) = PatternLibrary::parse(text);
+            assert!(!warnings.is_empty(), "{text:?}");
+            assert_eq!(library.patterns.len(), 1);
+            assert_eq!(library.find("aimed"), Some(0));
+        }
+    }
+}
@@ -0,0 +1,499 @@
+// INPUT RECORDING AND REPLAY
+//
+// A run is fully determined by the RNG seed at `start_game` and the input of
+// every tick after it, so a replay stores only those plus the final stats it
+// is expected to reproduce.
+//
+// File layout (all integers little-endian):
+//   magic     b"SDRP"
+//   version   u8
+//   mode      u8 (0 classic, 1 endless, 2 campaign followed by the night as u8,
+//             3 daily followed by the day as u32, 4 versus; absent in version 1, which is classic)
+//   players   u8, then shared lives u8 (0 or 1); absent before version 3 (one player)
+//   seed      u32
+//   ticks     u32, at most `MAX_REPLAY_TICKS`
+//   score, deliveries, max_combo   u32 each
+//   runs      (input bits, tick count) until `ticks` are covered. Before version 3
+//             the bits are a u8; from it they are a varint holding player N's
+//             bits in byte N. Tick counts are LEB128 varints.
+
+use crate::sim::{Input, Simulation};
+use crate::types::{RunMode, MAX_PLAYERS};
+
+pub const REPLAY_MAGIC: &[u8; 4] = b"SDRP";
+pub const REPLAY_VERSION: u8 = 3;
+/// Longest run a replay may hold: four hours at 60 ticks per second
+pub const MAX_REPLAY_TICKS: u32 = 4 * 60 * 60 * 60;
+
+// Input bits
+const BIT_UP: u8 = 1 << 0;
+const BIT_DOWN: u8 = 1 << 1;
+const BIT_DROP: u8 = 1 << 2;
+const BIT_PAUSE: u8 = 1 << 3;
+const BIT_CYCLE: u8 = 1 << 4;
+
+impl Input {
+    pub fn to_bits(&self) -> u8 {
+        let mut bits = 0;
+        if self.up { bits |= BIT_UP; }
+        if self.down { bits |= BIT_DOWN; }
+        if self.drop { bits |= BIT_DROP; }
+        if self.pause { bits |= BIT_PAUSE; }
+        if self.cycle { bits |= BIT_CYCLE; }
+        bits
+    }
+
+    pub fn from_bits(bits: u8) -> Self {
+        Self {
+            up: bits & BIT_UP != 0,
+            down: bits & BIT_DOWN != 0,
+            drop: bits & BIT_DROP != 0,
+            pause: bits & BIT_PAUSE != 0,
+            start: false,
+            cycle: bits & BIT_CYCLE != 0,
+        }
+    }
+}
+
+/// Final stats a replay must reproduce
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub struct RunSummary {
+    pub score: u32,
+    pub deliveries: u32,
+    pub max_combo: u32,
+}
+
+impl RunSummary {
+    pub fn of(sim: &Simulation) -> Self {
+        Self {
+            score: sim.score,
+            deliveries: sim.deliveries,
+            max_combo: sim.max_combo,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownMode(u8),
+    Truncated,
+    TooLong(u32),
+    BadVarint,
+    BadHex,
+    Mismatch { expected: RunSummary, actual: RunSummary },
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a replay file"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported replay version {v}"),
+            Self::UnknownMode(m) => write!(f, "unknown run mode {m}"),
+            Self::Truncated => write!(f, "replay data is truncated"),
+            Self::TooLong(ticks) => write!(f, "replay of {ticks} ticks is longer than the {MAX_REPLAY_TICKS} allowed"),
+            Self::BadVarint => write!(f, "replay data holds a malformed number"),
+            Self::BadHex => write!(f, "replay text is not valid hex"),
+            Self::Mismatch { expected, actual } => write!(
+                f,
+                "expected score {} / deliveries {} / max combo {}, got {} / {} / {}",
+                expected.score, expected.deliveries, expected.max_combo,
+                actual.score, actual.deliveries, actual.max_combo,
+            ),
+        }
+    }
+}
+
+/// One recorded run: the seed, who played, per-tick input bits and the expected result
+#[turbo::serialize]
+pub struct Replay {
+    pub seed: u32,
+    pub mode: RunMode,
+    pub players: u8,
+    pub shared_lives: bool,
+    pub frames: Vec<u16>,  // Player N's input bits in byte N
+    pub summary: RunSummary,
+}
+
+impl Replay {
+    /// Start recording a run that begins with the given RNG seed
+    pub fn new(seed: u32, mode: RunMode, players: u8, shared_lives: bool) -> Self {
+        Self { seed, mode, players, shared_lives, frames: vec![], summary: RunSummary::default() }
+    }
+
+    pub fn record(&mut self, inputs: &[Input]) {
+        let bits = inputs.iter().take(MAX_PLAYERS).enumerate().fold(0, |bits, (i, input)| bits | (input.to_bits() as u16) << (8 * i));
+        self.frames.push(bits);
+    }
+
+    /// Seal the recording with the stats the run ended on
+    pub fn finish(&mut self, sim: &Simulation) {
+        self.summary = RunSummary::of(sim);
+    }
+
+    /// Every player's input for a tick
+    pub fn inputs_at(&self, tick: usize) -> Option<[Input; MAX_PLAYERS]> {
+        self.frames.get(tick).map(|&bits| unpack(bits))
+    }
+
+    /// Start a fresh simulation at the recorded seed, ready for the first frame
+    pub fn start(&self) -> Simulation {
+        let mut sim = Simulation::new(self.seed);
+        sim.run_mode = self.mode;
+        sim.player_count = self.players;
+        sim.shared_lives = self.shared_lives;
+        sim.start_game();
+        sim
+    }
+
+    /// Play every recorded frame headlessly and return the resulting stats
+    pub fn run(&self) -> RunSummary {
+        let mut sim = self.start();
+        for &bits in &self.frames {
+            sim.step(&unpack(bits));
+        }
+        RunSummary::of(&sim)
+    }
+
+    /// Check that playing the replay reproduces its recorded stats
+    pub fn verify(&self) -> Result<RunSummary, ReplayError> {
+        let actual = self.run();
+        if actual == self.summary {
+            Ok(actual)
+        } else {
+            Err(ReplayError::Mismatch { expected: self.summary, actual })
+        }
+    }
+
+    // ========================================================================
+    // ENCODING
+    // ========================================================================
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(30 + self.frames.len() / 8);
+        out.extend_from_slice(REPLAY_MAGIC);
+        out.push(REPLAY_VERSION);
+        match self.mode {
+            RunMode::Classic => out.push(0),
+            RunMode::Endless => out.push(1),
+            RunMode::Campaign(night) => out.extend_from_slice(&[2, night]),
+            RunMode::Daily(day) => {
+                out.push(3);
+                out.extend_from_slice(&day.to_le_bytes());
+            }
+            RunMode::Versus => out.push(4),
+        }
+        out.extend_from_slice(&[self.players, self.shared_lives as u8]);
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        out.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        out.extend_from_slice(&self.summary.score.to_le_bytes());
+        out.extend_from_slice(&self.summary.deliveries.to_le_bytes());
+        out.extend_from_slice(&self.summary.max_combo.to_le_bytes());
+
+        // Run-length encode the input stream (held buttons repeat for many ticks)
+        let mut i = 0;
+        while i < self.frames.len() {
+            let bits = self.frames[i];
+            let mut run = 1;
+            while i + run < self.frames.len() && self.frames[i + run] == bits {
+                run += 1;
+            }
+            write_varint(&mut out, bits as u32);
+            write_varint(&mut out, run as u32);
+            i += run;
+        }
+        out
+    }
+
+    pub fn decode(data: &[u8]) -> Result<Self, ReplayError> {
+        let mut r = Reader { data, pos: 0 };
+        if r.take(4)? != REPLAY_MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = r.u8()?;
+        let mode = match version {
+            1 => RunMode::Classic,
+            2..=REPLAY_VERSION => match r.u8()? {
+                0 => RunMode::Classic,
+                1 => RunMode::Endless,
+                2 => RunMode::Campaign(r.u8()?),
+                3 => RunMode::Daily(r.u32()?),
+                4 => RunMode::Versus,
+                m => return Err(ReplayError::UnknownMode(m)),
+            },
+            _ => return Err(ReplayError::UnsupportedVersion(version)),
+        };
+        let (players, shared_lives) = match version {
+            1 | 2 => (1, false),
+            _ => (r.u8()?.clamp(1, MAX_PLAYERS as u8), r.u8()? != 0),
+        };
+        let seed = r.u32()?;
+        let ticks = r.u32()?;
+        if ticks > MAX_REPLAY_TICKS {
+            return Err(ReplayError::TooLong(ticks));
+        }
+        let ticks = ticks as usize;
+        let summary = RunSummary {
+            score: r.u32()?,
+            deliveries: r.u32()?,
+            max_combo: r.u32()?,
+        };
+
+        // Reserve no more than the input left could be worth; long runs grow it as they're read
+        let mut frames = Vec::with_capacity(ticks.min(r.remaining()));
+        while frames.len() < ticks {
+            let bits = match version {
+                1 | 2 => r.u8()? as u16,
+                _ => r.varint()? as u16,
+            };
+            let run = r.varint()? as usize;
+            if run == 0 || frames.len() + run > ticks {
+                return Err(ReplayError::Truncated);
+            }
+            frames.resize(frames.len() + run, bits);
+        }
+        Ok(Self { seed, mode, players, shared_lives, frames, summary })
+    }
+
+    /// Hex text form, for places that can only carry strings
+    pub fn to_hex(&self) -> String {
+        self.encode().iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    pub fn from_hex(text: &str) -> Result<Self, ReplayError> {
+        let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+        if !digits.len().is_multiple_of(2) {
+            return Err(ReplayError::BadHex);
+        }
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                let s = std::str::from_utf8(pair).map_err(|_| ReplayError::BadHex)?;
+                u8::from_str_radix(s, 16).map_err(|_| ReplayError::BadHex)
+            })
+            .collect::<Result<Vec<u8>, _>>()?;
+        Self::decode(&bytes)
+    }
+
+    /// Read a replay file: raw bytes, or the hex text the game exports
+    pub fn from_file(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if bytes.starts_with(REPLAY_MAGIC) {
+            Self::decode(bytes)
+        } else {
+            Self::from_hex(&String::from_utf8_lossy(bytes))
+        }
+    }
+}
+
+/// Split a frame back into each player's input
+fn unpack(bits: u16) -> [Input; MAX_PLAYERS] {
+    std::array::from_fn(|i| Input::from_bits((bits >> (8 * i)) as u8))
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ReplayError> {
+        let bytes = self.data.get(self.pos..self.pos.saturating_add(len)).ok_or(ReplayError::Truncated)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn u8(&mut self) -> Result<u8, ReplayError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, ReplayError> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn varint(&mut self) -> Result<u32, ReplayError> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.u8()?;
+            // The fifth byte only has room for the top four bits
+            if shift == 28 && byte > 0x0f {
+                return Err(ReplayError::BadVarint);
+            }
+            value |= ((byte & 0x7f) as u32) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ReplayError::BadVarint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Record a short scripted run: climbing, diving and dropping gifts every so often
+    fn recorded_run(mode: RunMode, players: u8) -> Replay {
+        let mut replay = Replay::new(0xC0FFEE, mode, players, false);
+        let mut sim = replay.start();
+        for tick in 0..900 {
+            let input = Input {
+                up: tick % 200 < 40,
+                down: (100..130).contains(&(tick % 200)),
+                drop: tick % 45 == 0,
+                cycle: tick == 300,
+                ..Input::default()
+            };
+            let inputs = vec![input; players as usize];
+            replay.record(&inputs);
+            sim.step(&inputs);
+        }
+        replay.finish(&sim);
+        replay
+    }
+
+    fn assert_same(a: &Replay, b: &Replay) {
+        assert_eq!(a.seed, b.seed);
+        assert_eq!(a.mode, b.mode);
+        assert_eq!(a.players, b.players);
+        assert_eq!(a.shared_lives, b.shared_lives);
+        assert_eq!(a.frames, b.frames);
+        assert_eq!(a.summary, b.summary);
+    }
+
+    /// A header up to the end of the summary, for the given version
+    fn header(version: u8, ticks: u32) -> Vec<u8> {
+        let mut out = REPLAY_MAGIC.to_vec();
+        out.push(version);
+        if version >= 2 {
+            out.push(1);  // Endless
+        }
+        if version >= 3 {
+            out.extend_from_slice(&[1, 0]);
+        }
+        out.extend_from_slice(&7u32.to_le_bytes());
+        out.extend_from_slice(&ticks.to_le_bytes());
+        for stat in [1200u32, 4, 2] {
+            out.extend_from_slice(&stat.to_le_bytes());
+        }
+        out
+    }
+
+    #[test]
+    fn round_trips_through_bytes_and_hex() {
+        for (mode, players) in [(RunMode::Classic, 1), (RunMode::Campaign(5), 1), (RunMode::Daily(20_000), 1), (RunMode::Versus, 2)] {
+            let replay = recorded_run(mode, players);
+            assert_same(&Replay::decode(&replay.encode()).unwrap(), &replay);
+            assert_same(&Replay::from_hex(&replay.to_hex()).unwrap(), &replay);
+            assert_same(&Replay::from_file(replay.to_hex().as_bytes()).unwrap(), &replay);
+        }
+    }
+
+    #[test]
+    fn verify_reproduces_the_recorded_run() {
+        let replay = recorded_run(RunMode::Classic, 1);
+        assert!(replay.summary.score > 0, "the scripted run should score something");
+        assert_eq!(replay.verify(), Ok(replay.summary));
+
+        let mut tampered = replay.clone();
+        tampered.summary.score += 1;
+        assert_eq!(
+            tampered.verify(),
+            Err(ReplayError::Mismatch { expected: tampered.summary, actual: replay.summary }),
+        );
+    }
+
+    #[test]
+    fn rejects_truncated_input() {
+        let bytes = recorded_run(RunMode::Classic, 1).encode();
+        for len in [0, 3, 10, bytes.len() - 1] {
+            assert_eq!(Replay::decode(&bytes[..len]).err(), Some(ReplayError::Truncated), "cut at {len}");
+        }
+    }
+
+    #[test]
+    fn rejects_a_bad_varint() {
+        let mut bytes = header(3, 1);
+        bytes.extend_from_slice(&[0xff; 5]);
+        assert_eq!(Replay::decode(&bytes).err(), Some(ReplayError::BadVarint));
+
+        let mut bytes = header(3, 1);
+        bytes.extend_from_slice(&[0, 0x80, 0x80, 0x80, 0x80, 0x10]);
+        assert_eq!(Replay::decode(&bytes).err(), Some(ReplayError::BadVarint));
+    }
+
+    #[test]
+    fn rejects_an_empty_run() {
+        let mut bytes = header(3, 1);
+        bytes.extend_from_slice(&[0, 0]);
+        assert_eq!(Replay::decode(&bytes).err(), Some(ReplayError::Truncated));
+    }
+
+    #[test]
+    fn rejects_runs_past_the_tick_count() {
+        let mut bytes = header(3, 10);
+        bytes.extend_from_slice(&[0, 11]);
+        assert_eq!(Replay::decode(&bytes).err(), Some(ReplayError::Truncated));
+    }
+
+    #[test]
+    fn rejects_an_oversized_tick_count_without_allocating_it() {
+        let mut bytes = header(3, u32::MAX);
+        bytes.extend_from_slice(&[0, 0x80, 0x80, 0x80, 0x80, 0x0f]);
+        assert_eq!(Replay::decode(&bytes).err(), Some(ReplayError::TooLong(u32::MAX)));
+
+        // The longest allowed run still decodes from a couple of bytes
+        let mut bytes = header(3, MAX_REPLAY_TICKS);
+        write_varint(&mut bytes, 0);
+        write_varint(&mut bytes, MAX_REPLAY_TICKS);
+        assert_eq!(Replay::decode(&bytes).unwrap().frames.len(), MAX_REPLAY_TICKS as usize);
+    }
+
+    #[test]
+    fn reads_version_1_and_2_headers() {
+        // Version 1: classic, one player, u8 input bits
+        let mut bytes = header(1, 5);
+        bytes.extend_from_slice(&[BIT_UP, 3, BIT_DROP, 2]);
+        let replay = Replay::decode(&bytes).unwrap();
+        assert_eq!(replay.mode, RunMode::Classic);
+        assert_eq!((replay.players, replay.shared_lives), (1, false));
+        assert_eq!(replay.seed, 7);
+        assert_eq!(replay.summary, RunSummary { score: 1200, deliveries: 4, max_combo: 2 });
+        assert_eq!(replay.frames, vec![BIT_UP as u16, BIT_UP as u16, BIT_UP as u16, BIT_DROP as u16, BIT_DROP as u16]);
+
+        // Version 2 adds the run mode
+        let mut bytes = header(2, 2);
+        bytes.extend_from_slice(&[BIT_DOWN, 2]);
+        let replay = Replay::decode(&bytes).unwrap();
+        assert_eq!(replay.mode, RunMode::Endless);
+        assert_eq!(replay.players, 1);
+        assert_eq!(replay.frames, vec![BIT_DOWN as u16; 2]);
+    }
+
+    #[test]
+    fn rejects_unknown_versions_and_modes() {
+        assert_eq!(Replay::decode(&header(REPLAY_VERSION + 1, 0)).err(), Some(ReplayError::UnsupportedVersion(REPLAY_VERSION + 1)));
+        let mut bytes = header(3, 0);
+        bytes[5] = 9;
+        assert_eq!(Replay::decode(&bytes).err(), Some(ReplayError::UnknownMode(9)));
+        assert_eq!(Replay::decode(b"NOPE").err(), Some(ReplayError::BadMagic));
+    }
+}
@@ -0,0 +1,895 @@
+// SAVE DATA
+//
+// Everything kept between sessions lives in one `SaveData` record written to
+// local storage as a checksummed, versioned blob:
+//
+// File layout (all integers little-endian):
+//   magic      b"SDSV"
+//   version    u8
+//   length     u32   payload size in bytes
+//   checksum   u32   FNV-1a of the payload
+//   payload    borsh-encoded `SaveData` for that version
+//
+// Saves from before this format were just the high score as four bytes; those
+// and older record versions are migrated on load. Anything unreadable falls
+// back to a fresh save.
+
+use crate::replay::Replay;
+use crate::types::RunMode;
+use turbo::borsh::{self, BorshDeserialize};
+
+pub const SAVE_MAGIC: &[u8; 4] = b"SDSV";
+pub const SAVE_VERSION: u8 = 8;
+pub const LEADERBOARD_SIZE: usize = 10;
+
+const HEADER_LEN: usize = 13;
+
+/// Player preferences
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub struct Settings {
+    pub music_volume: f32,     // 0.0 to 1.0
+    pub sfx_volume: f32,       // 0.0 to 1.0
+    pub screen_shake: bool,
+    pub flash_intensity: f32,  // 0.0 (off) to 1.0
+    pub aim_guide: bool,       // Show where the next gift will land
+    pub players: u8,           // 1, or 2 for co-op
+    pub shared_lives: bool,    // Co-op: one pool of hearts instead of one each
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            screen_shake: true,
+            flash_intensity: 1.0,
+            aim_guide: false,
+            players: 1,
+            shared_lives: false,
+        }
+    }
+}
+
+impl Settings {
+    /// Pull anything out of range (or NaN) back to something usable
+    fn sanitized(self) -> Self {
+        let unit = |v: f32| if v.is_nan() { 1.0 } else { v.clamp(0.0, 1.0) };
+        Self {
+            music_volume: unit(self.music_volume),
+            sfx_volume: unit(self.sfx_volume),
+            flash_intensity: unit(self.flash_intensity),
+            players: self.players.clamp(1, 2),
+            ..self
+        }
+    }
+}
+
+/// Totals across every run ever played
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub struct LifetimeStats {
+    pub runs: u32,
+    pub total_score: u64,
+    pub deliveries: u32,
+    pub krampus_survived: u32,
+    pub best_combo: u32,
+    pub highest_level: u32,
+}
+
+/// One line of the local leaderboard
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub struct ScoreEntry {
+    pub score: u32,
+    pub deliveries: u32,
+    pub max_combo: u32,
+    pub level: u32,
+    pub initials: [u8; 3],
+}
+
+impl ScoreEntry {
+    pub fn initials(&self) -> String {
+        self.initials.iter().map(|&c| c as char).collect()
+    }
+}
+
+/// Best result on one campaign night
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub struct NightRecord {
+    pub best_score: u32,  // Best score on a cleared run
+    pub stars: u8,        // 0 until cleared, then up to 3
+}
+
+/// Best daily challenge run, for the most recent day played
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub struct DailyBest {
+    pub day: u32,        // Days since the Unix epoch (0 = never played)
+    pub score: u32,
+    pub accuracy: u32,   // Percent, of the best-scoring run
+}
+
+/// Best run for one run mode and player count, kept to race as a ghost
+#[turbo::serialize]
+#[derive(PartialEq)]
+pub struct GhostRecord {
+    pub mode: RunMode,
+    pub players: u8,
+    pub score: u32,
+    pub replay: Vec<u8>,  // Encoded replay
+}
+
+impl GhostRecord {
+    fn is_for(&self, mode: RunMode, players: u8) -> bool {
+        self.mode == mode && self.players == players
+    }
+}
+
+#[turbo::serialize]
+pub struct SaveData {
+    pub high_score: u32,
+    pub first_play: bool,
+    pub settings: Settings,
+    pub stats: LifetimeStats,
+    pub leaderboard: Vec<ScoreEntry>,  // Best first, at most LEADERBOARD_SIZE
+    pub campaign: Vec<NightRecord>,    // By night, from December 1st; missing nights are unplayed
+    pub daily: DailyBest,
+    pub ghosts: Vec<GhostRecord>,      // Personal best ghost for each run mode and player count played
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            high_score: 0,
+            first_play: true,
+            settings: Settings::default(),
+            stats: LifetimeStats::default(),
+            leaderboard: vec![],
+            campaign: vec![],
+            daily: DailyBest::default(),
+            ghosts: vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    Truncated,
+    BadChecksum,
+    BadPayload,
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BadMagic => write!(f, "not a save file"),
+            Self::UnsupportedVersion(v) => write!(f, "unsupported save version {v}"),
+            Self::Truncated => write!(f, "save data is truncated"),
+            Self::BadChecksum => write!(f, "save data checksum does not match"),
+            Self::BadPayload => write!(f, "save data could not be decoded"),
+        }
+    }
+}
+
+impl SaveData {
+    /// Where a score would land on the leaderboard (0 = top), if it makes the cut
+    pub fn leaderboard_rank(&self, score: u32) -> Option<usize> {
+        if score == 0 { return None; }
+        let rank = self.leaderboard.iter().take_while(|e| e.score >= score).count();
+        (rank < LEADERBOARD_SIZE).then_some(rank)
+    }
+
+    /// Fold a finished run into the stats and leaderboard, returning its rank if it placed
+    pub fn record_run(&mut self, entry: ScoreEntry, krampus_survived: u32) -> Option<usize> {
+        self.record_stats(&entry, krampus_survived);
+        self.high_score = self.high_score.max(entry.score);
+
+        let rank = self.leaderboard_rank(entry.score)?;
+        self.leaderboard.insert(rank, entry);
+        self.leaderboard.truncate(LEADERBOARD_SIZE);
+        Some(rank)
+    }
+
+    /// Fold a finished run into the lifetime stats only (campaign nights stay off the leaderboard)
+    pub fn record_stats(&mut self, entry: &ScoreEntry, krampus_survived: u32) {
+        let stats = &mut self.stats;
+        stats.runs += 1;
+        stats.total_score += entry.score as u64;
+        stats.deliveries += entry.deliveries;
+        stats.krampus_survived += krampus_survived;
+        stats.best_combo = stats.best_combo.max(entry.max_combo);
+        stats.highest_level = stats.highest_level.max(entry.level);
+    }
+
+    /// How a 1-based campaign night has gone so far
+    pub fn night_record(&self, night: u8) -> NightRecord {
+        (night as usize).checked_sub(1).and_then(|i| self.campaign.get(i)).copied().unwrap_or_default()
+    }
+
+    /// Nights open to play out of `total`: the first, and each one after a cleared night
+    pub fn nights_unlocked(&self, total: u8) -> u8 {
+        let cleared = self.campaign.iter().take_while(|r| r.stars > 0).count() as u8;
+        (cleared + 1).min(total)
+    }
+
+    /// Keep the best of a finished campaign night, returning true if it beat the old best
+    pub fn record_night(&mut self, night: u8, score: u32, stars: u8) -> bool {
+        let Some(i) = (night as usize).checked_sub(1) else { return false };
+        if self.campaign.len() <= i {
+            self.campaign.resize(i + 1, NightRecord::default());
+        }
+        let record = &mut self.campaign[i];
+        record.stars = record.stars.max(stars);
+        let best = stars > 0 && score > record.best_score;
+        if best {
+            record.best_score = score;
+        }
+        best
+    }
+
+    /// The saved daily best if it is for `day`
+    pub fn daily_best(&self, day: u32) -> Option<DailyBest> {
+        (self.daily.day == day && day != 0).then_some(self.daily)
+    }
+
+    /// Keep the best daily challenge run of the day, returning true if this one is it
+    pub fn record_daily(&mut self, day: u32, score: u32, accuracy: u32) -> bool {
+        let best = self.daily_best(day).is_none_or(|old| score > old.score);
+        if best {
+            self.daily = DailyBest { day, score, accuracy };
+        }
+        best
+    }
+
+    /// The personal best ghost for a run mode and player count, if one has been saved
+    pub fn best_ghost(&self, mode: RunMode, players: u8) -> Option<Replay> {
+        let record = self.ghosts.iter().find(|g| g.is_for(mode, players))?;
+        Replay::decode(&record.replay).ok()
+    }
+
+    /// Keep a finished run as the ghost for its mode and player count if it
+    /// outscored the old one, returning true if it did
+    pub fn record_ghost(&mut self, replay: &Replay) -> bool {
+        let (mode, players, score) = (replay.mode, replay.players, replay.summary.score);
+        let best = self.ghosts.iter().find(|g| g.is_for(mode, players)).is_none_or(|old| score > old.score);
+        if best {
+            // Only the latest day's challenge can be raced again
+            let stale = |g: &GhostRecord| matches!((g.mode, mode), (RunMode::Daily(old), RunMode::Daily(new)) if old != new);
+            self.ghosts.retain(|g| !g.is_for(mode, players) && !stale(g));
+            self.ghosts.push(GhostRecord { mode, players, score, replay: replay.encode() });
+        }
+        best
+    }
+
+    // ========================================================================
+    // ENCODING
+    // ========================================================================
+
+    pub fn encode(&self) -> Vec<u8> {
+        let payload = borsh::to_vec(self).unwrap_or_default();
+        let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+        out.extend_from_slice(SAVE_MAGIC);
+        out.push(SAVE_VERSION);
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&checksum(&payload).to_le_bytes());
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// Read whatever is in local storage: nothing, a legacy high score, or a save record
+    pub fn decode(data: &[u8]) -> Result<Self, SaveError> {
+        match data.len() {
+            0 => return Ok(Self::default()),
+            4 => return Ok(Self::from_legacy(data)),
+            len if len < HEADER_LEN => return Err(SaveError::Truncated),
+            _ => {}
+        }
+        if &data[0..4] != SAVE_MAGIC {
+            return Err(SaveError::BadMagic);
+        }
+        let version = data[4];
+        if version == 0 || version > SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(version));
+        }
+        let len = u32::from_le_bytes([data[5], data[6], data[7], data[8]]) as usize;
+        let sum = u32::from_le_bytes([data[9], data[10], data[11], data[12]]);
+        let end = HEADER_LEN.checked_add(len).ok_or(SaveError::Truncated)?;
+        let payload = data.get(HEADER_LEN..end).ok_or(SaveError::Truncated)?;
+        if checksum(payload) != sum {
+            return Err(SaveError::BadChecksum);
+        }
+        let mut save = match version {
+            1 => v1::SaveData::try_from_slice(payload).map(Self::from),
+            2 => v2::SaveData::try_from_slice(payload).map(Self::from),
+            3 => v3::SaveData::try_from_slice(payload).map(Self::from),
+            4 => v4::SaveData::try_from_slice(payload).map(Self::from),
+            5 => v5::SaveData::try_from_slice(payload).map(Self::from),
+            6 => v6::SaveData::try_from_slice(payload).map(Self::from),
+            7 => v7::SaveData::try_from_slice(payload).map(Self::from),
+            _ => Self::try_from_slice(payload),
+        }
+        .map_err(|_| SaveError::BadPayload)?;
+        save.settings = save.settings.sanitized();
+        Ok(save)
+    }
+
+    /// The original save was the high score as four little-endian bytes
+    fn from_legacy(data: &[u8]) -> Self {
+        let high_score = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+        Self {
+            high_score,
+            // Anyone with a score has already seen the tutorial
+            first_play: high_score == 0,
+            ..Self::default()
+        }
+    }
+}
+
+/// Version 1 records, before screen shake and flash settings existed
+mod v1 {
+    use super::{LifetimeStats, ScoreEntry};
+
+    #[turbo::serialize]
+    pub struct Settings {
+        pub music_volume: f32,
+        pub sfx_volume: f32,
+    }
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: super::Settings {
+                    music_volume: old.settings.music_volume,
+                    sfx_volume: old.settings.sfx_volume,
+                    ..super::Settings::default()
+                },
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: vec![],
+                daily: super::DailyBest::default(),
+                ghosts: vec![],
+            }
+        }
+    }
+}
+
+/// Version 2 records, before the aim guide setting
+mod v2 {
+    use super::{LifetimeStats, ScoreEntry};
+
+    #[turbo::serialize]
+    pub struct Settings {
+        pub music_volume: f32,
+        pub sfx_volume: f32,
+        pub screen_shake: bool,
+        pub flash_intensity: f32,
+    }
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: super::Settings {
+                    music_volume: old.settings.music_volume,
+                    sfx_volume: old.settings.sfx_volume,
+                    screen_shake: old.settings.screen_shake,
+                    flash_intensity: old.settings.flash_intensity,
+                    ..super::Settings::default()
+                },
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: vec![],
+                daily: super::DailyBest::default(),
+                ghosts: vec![],
+            }
+        }
+    }
+}
+
+/// Version 3 records, before campaign progress
+mod v3 {
+    use super::v5::Settings;
+    use super::{LifetimeStats, ScoreEntry};
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: old.settings.into(),
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: vec![],
+                daily: super::DailyBest::default(),
+                ghosts: vec![],
+            }
+        }
+    }
+}
+
+/// Version 4 records, before the daily challenge
+mod v4 {
+    use super::v5::Settings;
+    use super::{LifetimeStats, NightRecord, ScoreEntry};
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+        pub campaign: Vec<NightRecord>,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: old.settings.into(),
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: old.campaign,
+                daily: super::DailyBest::default(),
+                ghosts: vec![],
+            }
+        }
+    }
+}
+
+/// Version 5 records, before co-op settings
+mod v5 {
+    use super::{DailyBest, LifetimeStats, NightRecord, ScoreEntry};
+
+    #[turbo::serialize]
+    pub struct Settings {
+        pub music_volume: f32,
+        pub sfx_volume: f32,
+        pub screen_shake: bool,
+        pub flash_intensity: f32,
+        pub aim_guide: bool,
+    }
+
+    impl From<Settings> for super::Settings {
+        fn from(old: Settings) -> Self {
+            Self {
+                music_volume: old.music_volume,
+                sfx_volume: old.sfx_volume,
+                screen_shake: old.screen_shake,
+                flash_intensity: old.flash_intensity,
+                aim_guide: old.aim_guide,
+                ..Self::default()
+            }
+        }
+    }
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+        pub campaign: Vec<NightRecord>,
+        pub daily: DailyBest,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: old.settings.into(),
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: old.campaign,
+                daily: old.daily,
+                ghosts: vec![],
+            }
+        }
+    }
+}
+
+/// Version 6 records, before the personal best ghost
+mod v6 {
+    use super::{DailyBest, LifetimeStats, NightRecord, ScoreEntry, Settings};
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+        pub campaign: Vec<NightRecord>,
+        pub daily: DailyBest,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: old.settings,
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: old.campaign,
+                daily: old.daily,
+                ghosts: vec![],
+            }
+        }
+    }
+}
+
+/// Version 7 records, with one personal best ghost whatever the mode or player count
+mod v7 {
+    use super::{DailyBest, GhostRecord, LifetimeStats, NightRecord, ScoreEntry, Settings};
+    use crate::replay::Replay;
+
+    #[turbo::serialize]
+    pub struct SaveData {
+        pub high_score: u32,
+        pub first_play: bool,
+        pub settings: Settings,
+        pub stats: LifetimeStats,
+        pub leaderboard: Vec<ScoreEntry>,
+        pub campaign: Vec<NightRecord>,
+        pub daily: DailyBest,
+        pub ghost: Vec<u8>,
+    }
+
+    impl From<SaveData> for super::SaveData {
+        fn from(old: SaveData) -> Self {
+            // The old ghost becomes the one for its own mode and player count
+            let ghosts = Replay::decode(&old.ghost)
+                .map(|r| GhostRecord { mode: r.mode, players: r.players, score: r.summary.score, replay: old.ghost })
+                .into_iter()
+                .collect();
+            Self {
+                high_score: old.high_score,
+                first_play: old.first_play,
+                settings: old.settings,
+                stats: old.stats,
+                leaderboard: old.leaderboard,
+                campaign: old.campaign,
+                daily: old.daily,
+                ghosts,
+            }
+        }
+    }
+}
+
+/// FNV-1a, enough to spot truncated or scribbled-over data
+fn checksum(data: &[u8]) -> u32 {
+    data.iter().fold(0x811c9dc5u32, |hash, &b| (hash ^ b as u32).wrapping_mul(0x01000193))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Frame a payload the way `encode` does, for an older version
+    fn wrap(version: u8, payload: Vec<u8>) -> Vec<u8> {
+        let mut out = SAVE_MAGIC.to_vec();
+        out.push(version);
+        out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        out.extend_from_slice(&checksum(&payload).to_le_bytes());
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    fn stats() -> LifetimeStats {
+        LifetimeStats { runs: 12, total_score: 45_000, deliveries: 90, krampus_survived: 3, best_combo: 8, highest_level: 4 }
+    }
+
+    fn leaderboard() -> Vec<ScoreEntry> {
+        vec![ScoreEntry { score: 9000, deliveries: 20, max_combo: 8, level: 4, initials: *b"ABC" }]
+    }
+
+    fn campaign() -> Vec<NightRecord> {
+        vec![NightRecord { best_score: 3000, stars: 2 }]
+    }
+
+    fn daily() -> DailyBest {
+        DailyBest { day: 20_000, score: 4200, accuracy: 75 }
+    }
+
+    fn decode_version(version: u8, payload: Vec<u8>) -> SaveData {
+        SaveData::decode(&wrap(version, payload)).unwrap_or_else(|err| panic!("version {version}: {err}"))
+    }
+
+    /// What every old record below carries over
+    fn assert_carried_over(save: &SaveData) {
+        assert_eq!(save.high_score, 9000);
+        assert!(!save.first_play);
+        assert_eq!(save.stats, stats());
+        assert_eq!(save.leaderboard, leaderboard());
+    }
+
+    fn v5_settings() -> v5::Settings {
+        v5::Settings { music_volume: 0.5, sfx_volume: 0.25, screen_shake: false, flash_intensity: 0.75, aim_guide: true }
+    }
+
+    fn v5_settings_migrated() -> Settings {
+        Settings { music_volume: 0.5, sfx_volume: 0.25, screen_shake: false, flash_intensity: 0.75, aim_guide: true, ..Settings::default() }
+    }
+
+    #[test]
+    fn round_trips_the_current_version() {
+        let save = SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: Settings { players: 2, shared_lives: true, ..v5_settings_migrated() },
+            stats: stats(),
+            leaderboard: leaderboard(),
+            campaign: campaign(),
+            daily: daily(),
+            ghosts: vec![GhostRecord { mode: RunMode::Endless, players: 2, score: 500, replay: vec![1, 2, 3] }],
+        };
+        let decoded = SaveData::decode(&save.encode()).unwrap();
+        assert_carried_over(&decoded);
+        assert_eq!(decoded.settings, save.settings);
+        assert_eq!(decoded.campaign, save.campaign);
+        assert_eq!(decoded.daily, save.daily);
+        assert_eq!(decoded.ghosts, save.ghosts);
+    }
+
+    #[test]
+    fn reads_nothing_as_a_fresh_save() {
+        let save = SaveData::decode(&[]).unwrap();
+        assert_eq!(save.high_score, 0);
+        assert!(save.first_play);
+    }
+
+    #[test]
+    fn migrates_the_legacy_high_score() {
+        let save = SaveData::decode(&1234u32.to_le_bytes()).unwrap();
+        assert_eq!(save.high_score, 1234);
+        assert!(!save.first_play);
+        assert_eq!(save.settings, Settings::default());
+        assert!(save.leaderboard.is_empty());
+
+        assert!(SaveData::decode(&[0; 4]).unwrap().first_play);
+    }
+
+    #[test]
+    fn migrates_version_1() {
+        let old = v1::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: v1::Settings { music_volume: 0.5, sfx_volume: 0.25 },
+            stats: stats(),
+            leaderboard: leaderboard(),
+        };
+        let save = decode_version(1, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.settings, Settings { music_volume: 0.5, sfx_volume: 0.25, ..Settings::default() });
+    }
+
+    #[test]
+    fn migrates_version_2() {
+        let old = v2::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: v2::Settings { music_volume: 0.5, sfx_volume: 0.25, screen_shake: false, flash_intensity: 0.75 },
+            stats: stats(),
+            leaderboard: leaderboard(),
+        };
+        let save = decode_version(2, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.settings, Settings { aim_guide: false, ..v5_settings_migrated() });
+    }
+
+    #[test]
+    fn migrates_version_3() {
+        let old = v3::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: v5_settings(),
+            stats: stats(),
+            leaderboard: leaderboard(),
+        };
+        let save = decode_version(3, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.settings, v5_settings_migrated());
+        assert!(save.campaign.is_empty());
+    }
+
+    #[test]
+    fn migrates_version_4() {
+        let old = v4::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: v5_settings(),
+            stats: stats(),
+            leaderboard: leaderboard(),
+            campaign: campaign(),
+        };
+        let save = decode_version(4, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.settings, v5_settings_migrated());
+        assert_eq!(save.campaign, campaign());
+        assert_eq!(save.daily, DailyBest::default());
+    }
+
+    #[test]
+    fn migrates_version_5() {
+        let old = v5::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: v5_settings(),
+            stats: stats(),
+            leaderboard: leaderboard(),
+            campaign: campaign(),
+            daily: daily(),
+        };
+        let save = decode_version(5, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.settings, v5_settings_migrated());
+        assert_eq!(save.settings.players, 1);
+        assert_eq!(save.campaign, campaign());
+        assert_eq!(save.daily, daily());
+    }
+
+    #[test]
+    fn migrates_version_6() {
+        let settings = Settings { players: 2, shared_lives: true, ..v5_settings_migrated() };
+        let old = v6::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings,
+            stats: stats(),
+            leaderboard: leaderboard(),
+            campaign: campaign(),
+            daily: daily(),
+        };
+        let save = decode_version(6, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.settings, settings);
+        assert_eq!(save.daily, daily());
+        assert!(save.ghosts.is_empty());
+    }
+
+    #[test]
+    fn migrates_version_7() {
+        let mut ghost = Replay::new(42, RunMode::Endless, 2, false);
+        ghost.summary.score = 7000;
+        let old = v7::SaveData {
+            high_score: 9000,
+            first_play: false,
+            settings: v5_settings_migrated(),
+            stats: stats(),
+            leaderboard: leaderboard(),
+            campaign: campaign(),
+            daily: daily(),
+            ghost: ghost.encode(),
+        };
+        let save = decode_version(7, borsh::to_vec(&old).unwrap());
+        assert_carried_over(&save);
+        assert_eq!(save.ghosts.len(), 1);
+        assert_eq!((save.ghosts[0].mode, save.ghosts[0].players, save.ghosts[0].score), (RunMode::Endless, 2, 7000));
+        assert_eq!(save.best_ghost(RunMode::Endless, 2).map(|r| r.seed), Some(42));
+        assert!(save.best_ghost(RunMode::Classic, 1).is_none());
+
+        let old = v7::SaveData { ghost: vec![], ..old };
+        assert!(decode_version(7, borsh::to_vec(&old).unwrap()).ghosts.is_empty());
+    }
+
+    fn run(mode: RunMode, players: u8, score: u32) -> Replay {
+        let mut replay = Replay::new(score, mode, players, false);
+        replay.summary.score = score;
+        replay
+    }
+
+    #[test]
+    fn keeps_a_ghost_per_mode_and_player_count() {
+        let mut save = SaveData::default();
+        assert!(save.record_ghost(&run(RunMode::Classic, 1, 1000)));
+        assert!(save.record_ghost(&run(RunMode::Classic, 2, 5000)));
+        assert!(save.record_ghost(&run(RunMode::Campaign(3), 1, 8000)));
+        assert!(!save.record_ghost(&run(RunMode::Classic, 1, 900)));
+        assert!(save.record_ghost(&run(RunMode::Classic, 1, 1100)));
+
+        assert_eq!(save.ghosts.len(), 3);
+        assert_eq!(save.best_ghost(RunMode::Classic, 1).map(|r| r.summary.score), Some(1100));
+        assert_eq!(save.best_ghost(RunMode::Classic, 2).map(|r| r.summary.score), Some(5000));
+        assert_eq!(save.best_ghost(RunMode::Campaign(3), 1).map(|r| r.summary.score), Some(8000));
+        assert!(save.best_ghost(RunMode::Campaign(4), 1).is_none());
+    }
+
+    #[test]
+    fn a_new_day_replaces_old_daily_ghosts() {
+        let mut save = SaveData::default();
+        save.record_ghost(&run(RunMode::Daily(100), 1, 4000));
+        save.record_ghost(&run(RunMode::Daily(100), 2, 6000));
+        save.record_ghost(&run(RunMode::Endless, 1, 3000));
+        assert!(save.record_ghost(&run(RunMode::Daily(101), 1, 10)));
+
+        assert!(save.best_ghost(RunMode::Daily(100), 1).is_none());
+        assert!(save.best_ghost(RunMode::Daily(100), 2).is_none());
+        assert!(save.best_ghost(RunMode::Daily(101), 1).is_some());
+        assert!(save.best_ghost(RunMode::Endless, 1).is_some());
+    }
+
+    #[test]
+    fn sanitizes_settings_on_load() {
+        let mut save = SaveData::default();
+        save.settings.music_volume = -1.0;
+        save.settings.sfx_volume = 3.0;
+        save.settings.players = 9;
+        let settings = SaveData::decode(&save.encode()).unwrap().settings;
+        assert_eq!(settings.music_volume, 0.0);
+        assert_eq!(settings.sfx_volume, 1.0);
+        assert_eq!(settings.players, 2);
+    }
+
+    #[test]
+    fn rejects_a_bad_checksum() {
+        let mut bytes = SaveData::default().encode();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert_eq!(SaveData::decode(&bytes).err(), Some(SaveError::BadChecksum));
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let bytes = SaveData::default().encode();
+        assert_eq!(SaveData::decode(&bytes[..bytes.len() - 1]).err(), Some(SaveError::Truncated));
+        assert_eq!(SaveData::decode(&bytes[..HEADER_LEN - 1]).err(), Some(SaveError::Truncated));
+    }
+
+    #[test]
+    fn rejects_a_length_past_the_end() {
+        let mut bytes = SaveData::default().encode();
+        bytes[5..9].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(SaveData::decode(&bytes).err(), Some(SaveError::Truncated));
+    }
+
+    #[test]
+    fn rejects_other_data() {
+        let mut bytes = SaveData::default().encode();
+        bytes[0] = b'X';
+        assert_eq!(SaveData::decode(&bytes).err(), Some(SaveError::BadMagic));
+
+        let mut bytes = SaveData::default().encode();
+        bytes[4] = SAVE_VERSION + 1;
+        assert_eq!(SaveData::decode(&bytes).err(), Some(SaveError::UnsupportedVersion(SAVE_VERSION + 1)));
+
+        assert_eq!(SaveData::decode(&wrap(SAVE_VERSION, vec![1, 2, 3])).err(), Some(SaveError::BadPayload));
+    }
+}
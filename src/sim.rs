@@ -0,0 +1,2093 @@
+// GAMEPLAY SIMULATION
+//
+// All game rules live here with no drawing, audio or input polling, so a run
+// can be stepped one tick at a time from plain Rust. The Turbo adapter in
+// lib.rs builds an `Input` each frame and reacts to the returned `Event`s.
+
+use crate::campaign::{Campaign, Night};
+use crate::daily;
+use crate::levels::{LevelConfig, LevelTable};
+use crate::patterns::{Aim, Motion};
+use crate::sound::Sfx;
+use crate::town::{BuildingKind, TownPlan, APARTMENT_SECTION};
+use crate::types::*;
+
+/// Ticks between lumps of coal from a rooftop thrower
+const COAL_THROW_INTERVAL: u32 = 100;
+/// A snow golem sits on the chimney stack, right of the house's centre
+const GOLEM_OFFSET_X: f32 = 15.0;
+/// Downward pull on falling gifts per tick
+const GIFT_GRAVITY: f32 = 0.15;
+/// Where gifts come down in the snow
+const GROUND_Y: f32 = SCREEN_H * 0.78 + 6.0;
+/// Longest flight the trajectory preview follows
+const PREVIEW_TICKS: u32 = 120;
+/// Naughty meter added for passing a nice house by
+const MISS_PENALTY: u32 = 20;
+/// Naughty meter added for a gift down the wrong kind of chimney
+const WRONG_HOUSE_PENALTY: u32 = 15;
+
+/// Snapshot of the buttons that matter for one tick
+#[turbo::serialize]
+#[derive(Copy, Default, PartialEq, Eq)]
+pub struct Input {
+    pub up: bool,     // Held
+    pub down: bool,   // Held
+    pub drop: bool,   // Just pressed
+    pub pause: bool,  // Just pressed
+    pub start: bool,  // Just pressed
+    pub cycle: bool,  // Just pressed: switch to the next gift type
+}
+
+/// Something that happened during a tick that the presentation layer may react to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Event {
+    ModeChanged { from: GameMode, to: GameMode },
+    GameStarted,
+    GiftDropped,
+    Delivered { x: f32, y: f32, points: u32, kind: GiftKind, requested: bool, grade: HitGrade },
+    GiftBroken { x: f32, y: f32 },   // A fragile gift clipped the chimney rim
+    GiftRefused { x: f32, y: f32 },  // Coal down a nice house's chimney
+    GiftBounced { x: f32, y: f32 },  // Glanced off a roof
+    GiftLanded { x: f32, y: f32 },   // Came down in the snow
+    NaughtyRewarded { x: f32, y: f32 },  // A present down a naughty house's chimney
+    LevelUp(u32),
+    KrampusWarning,
+    KrampusArrived,
+    KrampusSurvived { bonus: u32 },
+    PowerUpCollected { x: f32, y: f32, kind: PowerUpKind },
+    ShieldBlocked { x: f32, y: f32 },
+    SnowballThrown,
+    KrampusHurt { x: f32, y: f32 },
+    KrampusStage(u8),
+    KrampusDefeated { x: f32, y: f32, bonus: u32 },
+    EnemyHurt { x: f32, y: f32 },
+    EnemyDefeated { x: f32, y: f32, kind: EnemyKind, points: u32 },
+    PlayerHit,
+    AttackSent { from: u8, naughty: bool },  // Versus: a combo sent shots or a naughty house at the other player
+    Sfx(Sfx),
+}
+
+#[turbo::serialize]
+pub struct Simulation {
+    pub frame: u32,
+    pub mode: GameMode,
+    pub previous_mode: GameMode,  // For pause/resume
+
+    // Scrolling
+    pub scroll_x: f32,
+    pub scroll_speed: f32,
+
+    // Players (Santa's sleighs), one per gamepad in co-op
+    pub players: Vec<Player>,
+    pub player_count: u8,     // Sleighs in the next run, 1 or 2
+    pub shared_lives: bool,   // Co-op: a hit costs everyone a heart, and hearts heal everyone
+
+    // Chimneys (delivery targets)
+    pub chimneys: Vec<Chimney>,
+    pub next_chimney_spawn: f32,
+
+    // Endless mode street (town.toml)
+    pub run_mode: RunMode,
+    pub town: TownPlan,
+    pub town_seed: u32,       // Each chunk is laid out from its own stream of this
+    pub chunks_built: u32,
+    pub next_chunk_x: f32,    // Screen x where the next chunk starts
+    pub distance: f32,        // Pixels travelled this run
+    pub rivers: Vec<River>,
+
+    // Campaign nights (campaign.toml)
+    pub campaign: Campaign,
+    pub krampus_due: Vec<f32>,  // Distances still to come where Krampus attacks
+
+    // Falling gifts
+    pub gifts: Vec<FallingGift>,
+    pub next_chimney_id: u32,
+
+    // Enemies, Krampus included while he is attacking
+    pub enemies: Vec<Enemy>,
+    pub enemy_spawn_timer: u32,
+
+    // Krampus
+    pub krampus_attack_timer: u32,
+    pub krampus_duration: u32,
+    pub projectiles: Vec<Projectile>,
+    pub volleys: Vec<Volley>,  // Patterns still firing (patterns.toml)
+    pub krampus_warning: u32,
+
+    // Boss fight: snowballs thrown back at Krampus
+    pub krampus_max_health: u32,
+    pub krampus_stage: u8,       // 1-3, angrier as his health drops
+    pub snowballs: Vec<Snowball>,
+
+    // Stats (score and deliveries are the team's; each player also keeps their own)
+    pub score: u32,
+    pub deliveries: u32,
+    pub naughty_meter: u32,
+    pub level: u32,
+    pub gifts_dropped: u32,
+    pub grade_counts: [u32; 3],  // Deliveries per grade, in `HitGrade::ALL` order
+
+    // Combo system
+    pub combo_count: u32,
+    pub combo_timer: u32,  // Frames until combo resets
+    pub max_combo: u32,
+
+    // Power-ups
+    pub powerups: Vec<PowerUp>,
+    pub powerup_spawn_timer: u32,
+    pub active_powerups: Vec<ActivePowerUp>,
+
+    // RNG
+    pub rng: Rng,
+    pub powerup_rng: Rng,  // Power-ups' own stream in the daily challenge, so everyone gets the same ones
+
+    // Difficulty curve (levels.toml)
+    pub levels: LevelTable,
+
+    // Events raised during the current tick, drained by `step`
+    #[borsh(skip)]
+    #[serde(skip)]
+    events: Vec<Event>,
+}
+
+impl Simulation {
+    pub fn new(seed: u32) -> Self {
+        Self::with_levels(seed, LevelTable::embedded())
+    }
+
+    /// A simulation driven by a custom level table
+    pub fn with_levels(seed: u32, levels: LevelTable) -> Self {
+        let first = levels.get(1);
+        Self {
+            frame: 0,
+            mode: GameMode::Title,
+            previous_mode: GameMode::Title,
+
+            scroll_x: 0.0,
+            scroll_speed: first.scroll_speed,
+
+            players: vec![Player::new(PLAYER_X)],
+            player_count: 1,
+            shared_lives: false,
+
+            chimneys: vec![],
+            next_chimney_spawn: 100.0,
+
+            run_mode: RunMode::Classic,
+            town: TownPlan::embedded(),
+            town_seed: seed,
+            chunks_built: 0,
+            next_chunk_x: SCREEN_W,
+            distance: 0.0,
+            rivers: vec![],
+
+            campaign: Campaign::embedded(),
+            krampus_due: vec![],
+
+            gifts: vec![],
+            next_chimney_id: 0,
+
+            enemies: vec![],
+            enemy_spawn_timer: first.enemy_interval.0,
+
+            krampus_attack_timer: levels.first_krampus_delay,
+            krampus_duration: 0,
+            projectiles: vec![],
+            volleys: vec![],
+            krampus_warning: 0,
+
+            krampus_max_health: 0,
+            krampus_stage: 1,
+            snowballs: vec![],
+
+            score: 0,
+            deliveries: 0,
+            naughty_meter: 0,
+            level: 1,
+            gifts_dropped: 0,
+            grade_counts: [0; 3],
+
+            combo_count: 0,
+            combo_timer: 0,
+            max_combo: 0,
+
+            powerups: vec![],
+            powerup_spawn_timer: levels.first_powerup_delay,
+            active_powerups: vec![],
+
+            rng: Rng::new(seed),
+            powerup_rng: Rng::new(seed),
+            levels,
+
+            events: vec![],
+        }
+    }
+
+    /// Advance the simulation by one tick and return what happened.
+    /// `inputs` holds one entry per player; missing ones count as idle.
+    pub fn step(&mut self, inputs: &[Input]) -> Vec<Event> {
+        self.frame += 1;
+        self.store_previous_positions();
+
+        let idle = Input::default();
+        let input = |i: usize| inputs.get(i).unwrap_or(&idle);
+        // Either player can pause or start
+        let pause = inputs.iter().any(|i| i.pause);
+        let start = inputs.iter().any(|i| i.start);
+
+        // Handle pause toggle
+        if pause && self.in_run() {
+            self.toggle_pause();
+        }
+
+        if self.mode == GameMode::Paused {
+            return std::mem::take(&mut self.events);
+        }
+
+        for player in &mut self.players {
+            player.invincible_timer = player.invincible_timer.saturating_sub(1);
+        }
+        self.update_active_powerups();
+
+        self.update_combo();
+
+        match self.mode {
+            GameMode::Title => {
+                if start {
+                    self.emit(Event::Sfx(Sfx::Start));
+                    self.start_game();
+                }
+            }
+            GameMode::Delivering | GameMode::KrampusWarning => {
+                self.update_scroll();
+                self.move_players(inputs);
+                self.update_chimneys();
+                for i in 0..self.players.len() {
+                    if !self.players[i].flying() { continue; }
+                    if input(i).cycle {
+                        self.players[i].gift_kind = self.players[i].gift_kind.next();
+                    }
+                    if input(i).drop {
+                        self.drop_gift(i);
+                    }
+                }
+                self.update_gifts();
+                self.update_enemies();
+                self.update_projectiles();
+                self.check_krampus_trigger();
+                self.update_krampus_warning();
+                self.update_powerups();
+                self.check_night_over();
+                self.check_versus_over();
+            }
+            GameMode::Krampus => {
+                self.update_scroll();
+                self.move_players(inputs);
+                for i in 0..self.players.len() {
+                    if self.players[i].flying() && input(i).drop {
+                        self.throw_snowball(i);
+                    }
+                }
+                self.update_snowballs();
+                self.update_krampus();
+                self.update_projectiles();
+            }
+            GameMode::GameOver => {
+                if start {
+                    self.start_game();
+                }
+            }
+            GameMode::Paused => {}
+        }
+
+        std::mem::take(&mut self.events)
+    }
+
+    /// Whether a run is in progress (including while paused)
+    pub fn in_run(&self) -> bool {
+        self.mode.in_run()
+    }
+
+    /// Share of the gifts dropped this run that were delivered, in percent
+    pub fn accuracy(&self) -> u32 {
+        (self.grade_counts.iter().sum::<u32>() * 100).checked_div(self.gifts_dropped).unwrap_or(0)
+    }
+
+    /// Force the current run to end, e.g. when a replay runs out of input
+    pub fn end_run(&mut self) -> Vec<Event> {
+        self.transition(GameMode::GameOver);
+        std::mem::take(&mut self.events)
+    }
+
+    fn emit(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Move to another state if the state machine allows it.
+    /// Every mode change goes through here so the adapter sees exactly one
+    /// `ModeChanged` per transition and can run its exit/enter hooks.
+    fn transition(&mut self, to: GameMode) -> bool {
+        let from = self.mode;
+        if !from.can_transition(to) {
+            return false;
+        }
+        // Resume only into the state that was paused
+        if from == GameMode::Paused && to != GameMode::GameOver && to != self.previous_mode {
+            return false;
+        }
+        if to == GameMode::Paused {
+            self.previous_mode = from;
+        }
+        self.mode = to;
+        self.emit(Event::ModeChanged { from, to });
+        true
+    }
+
+    /// Remember where moving things were before this tick so drawing can
+    /// blend towards where they end up
+    fn store_previous_positions(&mut self) {
+        for player in &mut self.players {
+            player.prev_y = player.y;
+        }
+        for enemy in &mut self.enemies {
+            enemy.prev_x = enemy.x;
+            enemy.prev_y = enemy.y;
+        }
+        for gift in &mut self.gifts {
+            gift.prev_x = gift.x;
+            gift.prev_y = gift.y;
+        }
+        for proj in &mut self.projectiles {
+            proj.prev_x = proj.x;
+            proj.prev_y = proj.y;
+        }
+        for ball in &mut self.snowballs {
+            ball.prev_x = ball.x;
+            ball.prev_y = ball.y;
+        }
+    }
+
+    /// Begin a new run from the title or game over screen
+    pub fn start_game(&mut self) {
+        if self.mode != GameMode::Title && self.mode != GameMode::GameOver { return; }
+
+        if let RunMode::Daily(day) = self.run_mode {
+            self.rng = Rng::new(daily::seed(day));
+        }
+        self.powerup_rng = Rng::new(self.rng.seed ^ 0xB0B5_1ED5);
+
+        self.frame = 0;  // Spawn and fire timing keys off this, so replays need it fixed
+        self.scroll_x = 0.0;
+        self.scroll_speed = self.levels.get(1).scroll_speed;
+        // Versus is always a pair
+        if self.run_mode == RunMode::Versus {
+            self.player_count = MAX_PLAYERS as u8;
+        }
+        let count = (self.player_count as usize).clamp(1, MAX_PLAYERS);
+        self.players = PLAYER_XS[..count].iter().map(|&x| Player::new(x)).collect();
+
+        self.chimneys.clear();
+        self.next_chimney_spawn = 150.0;
+        self.town_seed = self.rng.seed;
+        self.chunks_built = 0;
+        self.next_chunk_x = SCREEN_W;
+        self.distance = 0.0;
+        self.rivers.clear();
+        self.gifts.clear();
+        self.next_chimney_id = 0;
+        self.projectiles.clear();
+        self.volleys.clear();
+        self.snowballs.clear();
+        self.powerups.clear();
+
+        self.enemies.clear();
+        self.enemy_spawn_timer = self.levels.get(1).enemy_interval.0;
+        self.krampus_attack_timer = self.levels.first_krampus_delay;
+        self.krampus_warning = 0;
+        self.krampus_duration = 0;
+
+        self.score = 0;
+        self.deliveries = 0;
+        self.naughty_meter = 0;
+        self.level = 1;
+        self.gifts_dropped = 0;
+        self.grade_counts = [0; 3];
+        self.active_powerups.clear();
+
+        // Combo system
+        self.combo_count = 0;
+        self.combo_timer = 0;
+        self.max_combo = 0;
+
+        // Power-ups
+        self.powerup_spawn_timer = self.levels.first_powerup_delay;
+
+        // A campaign night plays at one level, with Krampus at set points on the route
+        self.krampus_due.clear();
+        if let Some(night) = self.night() {
+            let (level, route) = (night.level, night.route);
+            self.krampus_due = night.krampus.iter().map(|at| at * route).collect();
+            self.level = level;
+            self.scroll_speed = self.config().scroll_speed;
+            self.enemy_spawn_timer = self.config().enemy_interval.0;
+        }
+
+        self.transition(GameMode::Delivering);
+        self.emit(Event::GameStarted);
+    }
+
+    // ========================================================================
+    // POWER-UP SYSTEM
+    // ========================================================================
+
+    /// Where power-up rolls come from: the main stream, or their own in the daily
+    /// challenge so they don't shift with everything else a player does
+    fn powerup_dice(&mut self) -> &mut Rng {
+        match self.run_mode {
+            RunMode::Daily(_) => &mut self.powerup_rng,
+            _ => &mut self.rng,
+        }
+    }
+
+    fn spawn_powerup(&mut self) {
+        let weights = &self.config().powerup_weights;
+        let total: u32 = weights.iter().map(|&(_, w)| w).sum();
+        let mut roll = self.powerup_dice().next_u32() % total.max(1);
+        let mut kind = PowerUpKind::Health;
+        for &(k, w) in &self.config().powerup_weights {
+            if roll < w {
+                kind = k;
+                break;
+            }
+            roll -= w;
+        }
+        let y = self.powerup_dice().range(40.0, SCREEN_H * 0.6);
+        let bob_offset = self.powerup_dice().range(0.0, std::f32::consts::TAU);
+        self.powerups.push(PowerUp {
+            x: SCREEN_W + 20.0,
+            y,
+            kind,
+            active: true,
+            bob_offset,
+        });
+    }
+
+    fn update_powerups(&mut self) {
+        // Spawn timer
+        if self.powerup_spawn_timer > 0 {
+            self.powerup_spawn_timer -= 1;
+        } else {
+            self.spawn_powerup();
+            let (min, max) = self.config().powerup_interval;
+            self.powerup_spawn_timer = min + self.powerup_dice().next_u32() % (max - min + 1);
+        }
+
+        // Collect collision data first to avoid borrow conflicts
+        let mut collected: Vec<(f32, f32, PowerUpKind, usize)> = Vec::new();
+        let scroll_speed = self.scroll_speed * self.time_scale();
+
+        for powerup in self.powerups.iter_mut() {
+            powerup.x -= scroll_speed;
+            powerup.bob_offset += 0.1;
+
+            let bob_y = powerup.y + (powerup.bob_offset.sin() * 5.0);
+            let catcher = self.players
+                .iter()
+                .position(|p| p.flying() && (p.x - powerup.x).abs() < 25.0 && (p.y - bob_y).abs() < 20.0);
+
+            if let Some(i) = catcher && powerup.active {
+                powerup.active = false;
+                collected.push((powerup.x, bob_y, powerup.kind, i));
+            }
+        }
+
+        // Now process collected power-ups
+        for (x, y, kind, i) in collected {
+            self.emit(Event::PowerUpCollected { x, y, kind });
+            self.collect_powerup(kind, i);
+        }
+
+        // Remove off-screen or collected power-ups
+        self.powerups.retain(|p| p.x > -30.0 && p.active);
+    }
+
+    /// Apply a power-up, stacking onto one already running. Everything but a
+    /// heart works for the whole team; a heart heals whoever caught it, or
+    /// everyone still flying when lives are shared.
+    fn collect_powerup(&mut self, kind: PowerUpKind, catcher: usize) {
+        if kind == PowerUpKind::Health {
+            let shared = self.lives_shared();
+            for (i, player) in self.players.iter_mut().enumerate() {
+                if player.flying() && (shared || i == catcher) {
+                    player.health = (player.health + 1).min(5);
+                }
+            }
+            self.emit(Event::Sfx(Sfx::Delivery));
+            return;
+        }
+
+        match self.active_powerups.iter_mut().find(|p| p.kind == kind) {
+            Some(active) => {
+                active.stacks = (active.stacks + 1).min(kind.max_stacks());
+                active.timer = kind.duration();
+            }
+            None => self.active_powerups.push(ActivePowerUp { kind, timer: kind.duration(), stacks: 1 }),
+        }
+        self.emit(Event::Sfx(Sfx::Survive));
+    }
+
+    /// Count down timed effects (shields last until used)
+    fn update_active_powerups(&mut self) {
+        for active in &mut self.active_powerups {
+            if active.kind.duration() > 0 {
+                active.timer = active.timer.saturating_sub(1);
+            }
+        }
+        self.active_powerups.retain(|p| p.kind.duration() == 0 || p.timer > 0);
+    }
+
+    /// Stacks of a running power-up (0 if it isn't running)
+    pub fn powerup_stacks(&self, kind: PowerUpKind) -> u32 {
+        self.active_powerups.iter().find(|p| p.kind == kind).map_or(0, |p| p.stacks)
+    }
+
+    pub fn has_powerup(&self, kind: PowerUpKind) -> bool {
+        self.powerup_stacks(kind) > 0
+    }
+
+    /// Spend a shield charge if there is one
+    fn use_shield(&mut self) -> bool {
+        let Some(i) = self.active_powerups.iter().position(|p| p.kind == PowerUpKind::Shield) else { return false };
+        self.active_powerups[i].stacks -= 1;
+        if self.active_powerups[i].stacks == 0 {
+            self.active_powerups.remove(i);
+        }
+        true
+    }
+
+    /// How fast the world moves this tick (slowed by slow-mo)
+    pub fn time_scale(&self) -> f32 {
+        if self.has_powerup(PowerUpKind::SlowMo) { 0.5 } else { 1.0 }
+    }
+
+    /// Points after the score multiplier
+    fn boosted(&self, points: u32) -> u32 {
+        points * (1 + self.powerup_stacks(PowerUpKind::Multiplier))
+    }
+
+    // ========================================================================
+    // COMBO SYSTEM
+    // ========================================================================
+
+    /// A delivery by `player` feeds the team combo and their own
+    fn add_combo(&mut self, player: usize) {
+        self.add_player_combo(player);
+        self.combo_count += 1;
+        // 3 seconds to maintain combo, 5 with the combo power-up
+        self.combo_timer = if self.has_powerup(PowerUpKind::ComboExtend) { 300 } else { 180 };
+        if self.combo_count > self.max_combo {
+            self.max_combo = self.combo_count;
+        }
+
+        // Bonus points for combos
+        let bonus = match self.combo_count {
+            0 | 1 => 0,
+            2 => 50,
+            3 => 100,
+            4 => 200,
+            5..=9 => 300,
+            _ => 500,
+        };
+        self.score += self.boosted(bonus);
+    }
+
+    /// A player's own combo, which in versus sends an attack every few steps
+    fn add_player_combo(&mut self, i: usize) {
+        let window = if self.has_powerup(PowerUpKind::ComboExtend) { 300 } else { 180 };
+        let Some(player) = self.players.get_mut(i) else { return };
+        player.combo += 1;
+        player.combo_timer = window;
+        player.max_combo = player.max_combo.max(player.combo);
+        let combo = player.combo;
+        if self.run_mode == RunMode::Versus && combo.is_multiple_of(VERSUS_SEND_EVERY) {
+            self.send_attack(i, combo / VERSUS_SEND_EVERY);
+        }
+    }
+
+    fn update_combo(&mut self) {
+        if self.combo_timer > 0 {
+            self.combo_timer -= 1;
+        } else if self.combo_count > 0 {
+            self.combo_count = 0;
+        }
+        for player in &mut self.players {
+            if player.combo_timer > 0 {
+                player.combo_timer -= 1;
+            } else {
+                player.combo = 0;
+            }
+        }
+    }
+
+    /// Drop the team combo, and the own combo of each player who wasted a gift
+    fn break_combo(&mut self, players: &[u8]) {
+        self.combo_count = 0;
+        self.combo_timer = 0;
+        for &i in players {
+            if let Some(player) = self.players.get_mut(i as usize) {
+                player.combo = 0;
+                player.combo_timer = 0;
+            }
+        }
+    }
+
+    // ========================================================================
+    // VERSUS
+    // ========================================================================
+
+    /// Send the other player something to deal with: odd waves are a fan of
+    /// Krampus shots only they can be hit by, even waves a naughty house in their lane
+    fn send_attack(&mut self, from: usize, wave: u32) {
+        let target = 1 - from.min(1);
+        let Some(&Player { x, y, .. }) = self.players.get(target) else { return };
+        let naughty = wave.is_multiple_of(2);
+        if naughty {
+            self.players[target].naughty_due += 1;
+        } else {
+            let (sx, sy) = (SCREEN_W + 10.0, y);
+            let speed = self.config().projectile_speed;
+            let aim = (y - sy).atan2(x - sx);
+            for offset in [-0.15, 0.0, 0.15] {
+                self.spawn_projectile(EnemyKind::Krampus, sx, sy, aim + offset, speed, Motion::default());
+                if let Some(shot) = self.projectiles.last_mut() {
+                    shot.target = Some(target as u8);
+                }
+            }
+        }
+        self.emit(Event::AttackSent { from: from as u8, naughty });
+        self.emit(Event::Sfx(Sfx::Warning));
+    }
+
+    /// The race is over once someone reaches the target
+    fn check_versus_over(&mut self) {
+        if self.run_mode != RunMode::Versus || !self.in_run() { return; }
+        if self.players.iter().any(|p| p.deliveries >= VERSUS_TARGET) {
+            self.transition(GameMode::GameOver);
+            self.emit(Event::Sfx(Sfx::Survive));
+        }
+    }
+
+    /// Who won a versus race: first to the target, else the last one flying,
+    /// else whoever delivered more (None for a draw or outside versus)
+    pub fn versus_winner(&self) -> Option<usize> {
+        if self.run_mode != RunMode::Versus || self.players.len() < 2 { return None; }
+        let (a, b) = (&self.players[0], &self.players[1]);
+        let key = |p: &Player| (p.deliveries >= VERSUS_TARGET, p.flying(), p.deliveries, p.score);
+        match key(a).cmp(&key(b)) {
+            std::cmp::Ordering::Greater => Some(0),
+            std::cmp::Ordering::Less => Some(1),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+
+    /// Whether a hit costs every player a heart
+    fn lives_shared(&self) -> bool {
+        self.shared_lives && self.run_mode != RunMode::Versus
+    }
+
+    // ========================================================================
+    // PROGRESSIVE DIFFICULTY
+    // ========================================================================
+
+    /// Settings for the current level
+    pub fn config(&self) -> &LevelConfig {
+        self.levels.get(self.level)
+    }
+
+    /// The campaign night being flown, if this is a campaign run
+    pub fn night(&self) -> Option<&Night> {
+        match self.run_mode {
+            RunMode::Campaign(night) => self.campaign.get(night),
+            _ => None,
+        }
+    }
+
+    /// Whether a campaign night reached the end of its route with enough deliveries
+    pub fn night_cleared(&self) -> bool {
+        self.night().is_some_and(|n| self.distance >= n.route && self.deliveries >= n.target)
+    }
+
+    /// Stars earned on a campaign night so far (0 until it is cleared)
+    pub fn night_stars(&self) -> u8 {
+        self.night().map_or(0, |n| n.rating(self.night_cleared(), self.score))
+    }
+
+    /// A campaign night ends once the route is flown, cleared or not
+    fn check_night_over(&mut self) {
+        if self.mode != GameMode::Delivering { return; }
+        let Some(route) = self.night().map(|n| n.route) else { return };
+        if self.distance < route { return; }
+
+        self.transition(GameMode::GameOver);
+        self.emit(Event::Sfx(if self.night_cleared() { Sfx::Survive } else { Sfx::GameOver }));
+    }
+
+    /// The one place the level advances: every `deliveries_per_level` deliveries,
+    /// or every `level_distance` pixels in endless mode. Campaign nights keep theirs.
+    fn check_level_up(&mut self) {
+        let target = match self.run_mode {
+            RunMode::Classic | RunMode::Daily(_) | RunMode::Versus => 1 + self.deliveries / self.levels.deliveries_per_level,
+            RunMode::Endless => 1 + (self.distance / self.town.level_distance) as u32,
+            RunMode::Campaign(_) => return,
+        };
+        while self.level < target {
+            self.level += 1;
+            self.scroll_speed = self.config().scroll_speed;
+            self.emit(Event::LevelUp(self.level));
+            self.emit(Event::Sfx(Sfx::Survive));  // Jingle for level up
+        }
+    }
+
+    // ========================================================================
+    // PAUSE SYSTEM
+    // ========================================================================
+
+    fn toggle_pause(&mut self) {
+        if self.mode == GameMode::Paused {
+            self.transition(self.previous_mode);
+        } else {
+            self.transition(GameMode::Paused);
+        }
+    }
+
+    // ========================================================================
+    // PLAYER MOVEMENT
+    // ========================================================================
+
+    fn move_players(&mut self, inputs: &[Input]) {
+        for (i, player) in self.players.iter_mut().enumerate() {
+            if !player.flying() { continue; }
+            let input = inputs.get(i).copied().unwrap_or_default();
+
+            // Vertical movement only
+            if input.up {
+                player.vel_y = -PLAYER_SPEED;
+                player.tilt = -8.0; // Tilt up
+            } else if input.down {
+                player.vel_y = PLAYER_SPEED;
+                player.tilt = 8.0; // Tilt down
+            } else {
+                player.vel_y *= 0.85; // Deceleration
+                player.tilt *= 0.8;   // Return to level
+            }
+
+            player.y += player.vel_y;
+            player.y = player.y.clamp(20.0, SCREEN_H - 30.0);
+        }
+    }
+
+    /// The flying player closest to a point, for enemies picking a target
+    /// (player 0 if nobody is flying)
+    fn nearest_player(&self, x: f32, y: f32) -> usize {
+        let dist = |p: &Player| (p.x - x).powi(2) + (p.y - y).powi(2);
+        self.players
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| p.flying())
+            .min_by(|(_, a), (_, b)| dist(a).total_cmp(&dist(b)))
+            .map_or(0, |(i, _)| i)
+    }
+
+    // CHIMNEY SYSTEM
+
+    fn spawn_chimney(&mut self) {
+        // Spawn chimneys on the ground (78% of screen height)
+        let ground_y = SCREEN_H * 0.78;
+        let y = self.rng.range(ground_y - 30.0, ground_y - 10.0);
+        // Random house style (0-2)
+        let style = (self.rng.range(0.0, 3.0) as u8).min(2);
+        let (mut house, mut request) = roll_house(&mut self.rng, self.levels.get(self.level));
+
+        // Versus: houses take turns between the lanes, and a sent naughty house takes the next one
+        let versus = self.run_mode == RunMode::Versus;
+        let lane = versus.then_some((self.next_chimney_id % 2) as u8);
+        if let Some(player) = lane.and_then(|l| self.players.get_mut(l as usize))
+            && player.naughty_due > 0
+        {
+            player.naughty_due -= 1;
+            (house, request) = (HouseKind::Naughty, None);
+        }
+        self.chimneys.push(Chimney {
+            id: self.next_chimney_id,
+            x: SCREEN_W + 40.0,
+            y,
+            delivered: false,
+            blocked: false,
+            house,
+            request,
+            style,
+            lane,
+        });
+        self.next_chimney_id += 1;
+
+        // Next chimney spawn distance (varies, more space for larger screen; closer
+        // in versus, where each player only gets every other house)
+        let (min, max) = self.config().chimney_spacing;
+        self.next_chimney_spawn = self.rng.range(min, max) * if versus { 0.6 } else { 1.0 };
+    }
+
+    /// Lay out the next chunk of the endless street just past the right edge.
+    /// Everything comes from the chunk's own stream, so a seed always builds the
+    /// same town however the run is played.
+    fn build_chunk(&mut self) {
+        let mut rng = Rng::new(self.town_seed ^ self.chunks_built.wrapping_mul(0x9E37_79B9));
+        let start = self.next_chunk_x;
+        let travelled = self.distance + start;
+        let chunk = self.town.pick(&mut rng, travelled).clone();
+        let config = self.levels.get(self.level).clone();
+        let ground_y = SCREEN_H * 0.78;
+
+        if let Some((x, width)) = chunk.river {
+            self.rivers.push(River { x: start + x, width });
+        }
+        for building in &chunk.buildings {
+            let offset = rng.range(-building.jitter, building.jitter);
+            if rng.range(0.0, 1.0) >= building.chance {
+                continue;
+            }
+            let x = start + building.x + offset;
+            let (style, y, count) = match building.kind {
+                BuildingKind::House => {
+                    let y = rng.range(ground_y - 30.0, ground_y - 10.0);
+                    ((rng.range(0.0, 3.0) as u8).min(2), y, 1)
+                }
+                BuildingKind::Church => (3, ground_y - 30.0, 1),
+                BuildingKind::Apartment => (4, ground_y - 52.0, building.chimneys),
+            };
+            for section in 0..count {
+                let (house, request) = roll_house(&mut rng, &config);
+                self.chimneys.push(Chimney {
+                    id: self.next_chimney_id,
+                    x: x + section as f32 * APARTMENT_SECTION,
+                    y,
+                    delivered: false,
+                    blocked: false,
+                    house,
+                    request,
+                    style,
+                    lane: None,
+                });
+                self.next_chimney_id += 1;
+            }
+        }
+
+        self.next_chunk_x += chunk.width + self.town.gap_at(&mut rng, travelled);
+        self.chunks_built += 1;
+    }
+
+    fn update_chimneys(&mut self) {
+        // Spawn new chimneys
+        match self.run_mode {
+            RunMode::Classic | RunMode::Campaign(_) | RunMode::Daily(_) | RunMode::Versus => {
+                if self.chimneys.last().is_none_or(|c| c.x < SCREEN_W - self.next_chimney_spawn) {
+                    self.spawn_chimney();
+                }
+            }
+            RunMode::Endless => {
+                while self.next_chunk_x < SCREEN_W + 40.0 {
+                    self.build_chunk();
+                }
+            }
+        }
+
+        // Track missed chimneys
+        let mut missed_count = 0;
+
+        // Update positions and remove off-screen (only nice houses are owed a gift)
+        self.chimneys.retain(|c| {
+            if c.x < -40.0 {
+                if !c.delivered && c.house == HouseKind::Nice {
+                    missed_count += 1;
+                }
+                false
+            } else {
+                true
+            }
+        });
+
+        // Increase naughty meter for missed deliveries
+        self.naughty_meter = (self.naughty_meter + missed_count * MISS_PENALTY).min(100);
+
+        // Move chimneys
+        let scroll_speed = self.scroll_speed * self.time_scale();
+        for chimney in &mut self.chimneys {
+            chimney.x -= scroll_speed;
+        }
+
+        self.distance += scroll_speed;
+        if self.run_mode == RunMode::Endless {
+            for river in &mut self.rivers {
+                river.x -= scroll_speed;
+            }
+            self.rivers.retain(|r| r.x + r.width > -40.0);
+            self.next_chunk_x -= scroll_speed;
+            self.check_level_up();
+        }
+    }
+
+
+    // GIFT DROPPING
+
+
+    fn drop_gift(&mut self, player: usize) {
+        let (player_x, gift_kind) = (self.players[player].x, self.players[player].gift_kind);
+        // Find the nearest chimney ahead that wants this gift (increased range for easier aiming)
+        let mut best_chimney: Option<u32> = None;
+        let mut best_dist = f32::MAX;
+
+        for chimney in &self.chimneys {
+            // Larger detection window: 150 pixels ahead
+            if chimney.open()
+                && chimney.takes(player as u8)
+                && chimney.house.wants(gift_kind)
+                && chimney.x > player_x - 20.0
+                && chimney.x < player_x + 150.0
+            {
+                let dist = (chimney.x - player_x).abs();
+                if dist < best_dist {
+                    best_dist = dist;
+                    best_chimney = Some(chimney.id);
+                }
+            }
+        }
+
+        // Drop a gift, or a fan of them with the multi-gift power-up. Only the
+        // middle one is a drop; the rest are extras that count if they deliver.
+        let spread = self.powerup_stacks(PowerUpKind::MultiGift) as i32;
+        for offset in -spread..=spread {
+            let mut gift = self.new_gift(player, offset as f32 * 14.0);
+            gift.target_chimney = best_chimney;
+            gift.extra = offset != 0;
+            gift.fan = self.gifts_dropped;
+            self.gifts.push(gift);
+        }
+        self.gifts_dropped += 1;
+        self.players[player].gifts_dropped += 1;
+
+        self.emit(Event::GiftDropped);
+        self.emit(Event::Sfx(Sfx::Drop));
+    }
+
+    /// A gift of the player's selected kind leaving their sleigh now, carrying its climb or dive and tilt
+    fn new_gift(&self, player: usize, offset_x: f32) -> FallingGift {
+        let p = &self.players[player];
+        let (x, y) = (p.x + 8.0 + offset_x, p.y + 12.0);
+        FallingGift {
+            kind: p.gift_kind,
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vel_x: -self.scroll_speed * 0.3 + p.tilt * 0.04,
+            vel_y: 1.0 + p.vel_y * 0.5,
+            target_chimney: None,
+            outcome: GiftOutcome::Falling,
+            owner: player as u8,
+            fan: 0,
+            extra: false,
+        }
+    }
+
+    /// Current wind on gifts and snow: the level's base wind swinging through its gusts
+    pub fn wind(&self) -> f32 {
+        let config = self.config();
+        config.wind + config.gust * (self.frame as f32 * 0.013).sin()
+    }
+
+    /// Where a gift this player dropped now would go: points along its path, and
+    /// whether it ends in a chimney that wants it (ignores the magnet and enemies in the way)
+    pub fn predict_drop(&self, player: usize) -> (Vec<(f32, f32)>, bool) {
+        let mut gift = self.new_gift(player, 0.0);
+        let mut chimneys = self.chimneys.clone();
+        let (wind, time_scale) = (self.wind(), self.time_scale());
+        let scroll_speed = self.scroll_speed * time_scale;
+        let hitbox = self.config().chimney_hitbox;
+
+        let mut path = vec![(gift.x, gift.y)];
+        for _ in 0..PREVIEW_TICKS {
+            gift_step(&mut gift, wind, time_scale);
+            path.push((gift.x, gift.y));
+            for chimney in chimneys.iter().filter(|c| c.takes(gift.owner)) {
+                if chimney.open() && in_chimney(gift.x - chimney.x, gift.y - chimney.y, hitbox) {
+                    return (path, chimney.house.wants(gift.kind));
+                }
+                if on_roof(chimney, gift.x, gift.y, hitbox) {
+                    return (path, false);
+                }
+            }
+            if gift.y >= GROUND_Y {
+                break;
+            }
+            for chimney in &mut chimneys {
+                chimney.x -= scroll_speed;
+            }
+        }
+        (path, false)
+    }
+
+    fn update_gifts(&mut self) {
+        let time_scale = self.time_scale();
+        let wind = self.wind();
+        let hitbox = self.config().chimney_hitbox;
+        let base_points = self.boosted(100 + self.level * 10);
+        let mut score_gained = 0u32;
+        let mut earned: Vec<(usize, u32)> = Vec::new();  // Points per delivery, by who dropped it
+        let mut delivered: Vec<Event> = Vec::new();  // For particles
+        let mut spoiled: Vec<Event> = Vec::new();    // Broken, unwanted, bounced or landed gifts
+        let mut wrong_house = 0u32;
+
+        // Rooftop enemies can be knocked off with a gift
+        let targets: Vec<(usize, f32, f32, f32)> = self.enemies
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.active && e.kind.on_rooftop())
+            .map(|(i, e)| (i, e.x, e.y, e.kind.radius() + 6.0))
+            .collect();
+        let mut enemy_hits = vec![];
+        let magnet = self.has_powerup(PowerUpKind::Magnet);
+
+        for gift in &mut self.gifts {
+            if !gift.outcome.in_flight() { continue; }
+
+            // Gift falls with arc
+            gift_step(gift, wind, time_scale);
+
+            if gift.outcome == GiftOutcome::Falling {
+                // Magnet: drift towards the chimney it was dropped at, or else the
+                // nearest open one below that wants it (a golem's chimney can't take gifts)
+                if magnet {
+                    let below = self.chimneys
+                        .iter()
+                        .filter(|c| c.open() && c.takes(gift.owner) && c.house.wants(gift.kind) && c.y > gift.y - 10.0 && (c.x - gift.x).abs() < 90.0);
+                    let pull = below
+                        .clone()
+                        .find(|c| Some(c.id) == gift.target_chimney)
+                        .or_else(|| below.min_by(|a, b| (a.x - gift.x).abs().total_cmp(&(b.x - gift.x).abs())));
+                    if let Some(chimney) = pull {
+                        gift.x += (chimney.x - gift.x).clamp(-1.5, 1.5);
+                    }
+                }
+
+                if let Some(&(i, ..)) = targets.iter().find(|&&(_, ex, ey, r)| (gift.x - ex).powi(2) + (gift.y - ey).powi(2) < r * r) {
+                    gift.outcome = GiftOutcome::HitEnemy;
+                    enemy_hits.push((i, gift.owner as usize));
+                    continue;
+                }
+
+                // Check collision with chimneys, then the roofs around them
+                for chimney in self.chimneys.iter_mut().filter(|c| c.takes(gift.owner)) {
+                    let dx = gift.x - chimney.x;
+                    let dy = gift.y - chimney.y;
+                    if !chimney.open() || !in_chimney(dx, dy, hitbox) {
+                        if on_roof(chimney, gift.x, gift.y, hitbox) {
+                            // Glance off and tumble away from the ridge
+                            gift.outcome = GiftOutcome::Bouncing;
+                            gift.y = chimney.roof_y(dx).unwrap_or(gift.y);
+                            gift.vel_y = -gift.vel_y * 0.4;
+                            gift.vel_x += dx.signum() * 0.8;
+                            spoiled.push(Event::GiftBounced { x: gift.x, y: gift.y });
+                            break;
+                        }
+                        continue;
+                    }
+
+                    let (x, y) = (chimney.x, chimney.y);
+                    match (chimney.house, gift.kind) {
+                        (HouseKind::Nice, GiftKind::Coal) => {
+                            gift.outcome = GiftOutcome::WrongHouse;
+                            wrong_house += 1;
+                            spoiled.push(Event::GiftRefused { x, y });
+                        }
+                        (HouseKind::Naughty, kind) if kind != GiftKind::Coal => {
+                            // They got their presents anyway, and it's on Santa's record
+                            gift.outcome = GiftOutcome::WrongHouse;
+                            wrong_house += 1;
+                            chimney.delivered = true;
+                            spoiled.push(Event::NaughtyRewarded { x, y });
+                        }
+                        (_, GiftKind::Fragile) if HitGrade::for_offset(dx, hitbox) == HitGrade::Good => {
+                            gift.outcome = GiftOutcome::Broken;
+                            spoiled.push(Event::GiftBroken { x: gift.x, y: gift.y });
+                        }
+                        (_, kind) => {
+                            // Closer to the middle pays more, and bringing what the
+                            // house asked for pays half again on top
+                            gift.outcome = GiftOutcome::Delivered;
+                            let grade = HitGrade::for_offset(dx, hitbox);
+                            let requested = chimney.request == Some(kind);
+                            let mut points = base_points * kind.value() * grade.percent() / 100;
+                            if requested { points += points / 2; }
+                            score_gained += points;
+                            earned.push((gift.owner as usize, points));
+                            chimney.delivered = true;
+                            delivered.push(Event::Delivered { x, y, points, kind, requested, grade });
+                        }
+                    }
+                    break;
+                }
+                if !gift.outcome.in_flight() { continue; }
+            }
+
+            // Into the snow, or off the screen
+            if gift.y >= GROUND_Y {
+                gift.outcome = GiftOutcome::Landed;
+                spoiled.push(Event::GiftLanded { x: gift.x, y: GROUND_Y });
+            } else if gift.x < -20.0 || gift.x > SCREEN_W + 20.0 {
+                gift.outcome = GiftOutcome::Lost;
+            }
+        }
+
+        // A fan counts as one drop: whichever of its gifts delivers first stands for
+        // it, and the rest are extras that only count (as drops of their own) if they deliver
+        let mut extras_delivered: Vec<usize> = Vec::new();  // Owners
+        let mut delivering: Vec<usize> = (0..self.gifts.len()).filter(|&i| self.gifts[i].outcome == GiftOutcome::Delivered).collect();
+        delivering.sort_by_key(|&i| self.gifts[i].extra);
+        for i in delivering {
+            let (fan, owner) = (self.gifts[i].fan, self.gifts[i].owner);
+            let main_open = self.gifts.iter().any(|g| g.fan == fan && g.owner == owner && !g.extra && g.outcome != GiftOutcome::Delivered);
+            if self.gifts[i].extra && !main_open {
+                extras_delivered.push(owner as usize);
+                continue;
+            }
+            for (j, gift) in self.gifts.iter_mut().enumerate() {
+                if gift.fan == fan && gift.owner == owner {
+                    gift.extra = j != i;
+                }
+            }
+        }
+
+        // Every gift not in flight any more finished this tick (extras that missed don't count)
+        let missed = |g: &&FallingGift| !g.extra && g.outcome.breaks_combo();
+        let combo_broken = self.gifts.iter().any(|g| missed(&g));
+        let broken_by: Vec<u8> = self.gifts.iter().filter(missed).map(|g| g.owner).collect();
+        let landed = self.gifts.iter().any(|g| !g.extra && g.outcome == GiftOutcome::Landed);
+        let deliveries_made = delivered.len() as u32;
+
+        // Apply score and deliveries
+        self.score += score_gained;
+        self.deliveries += deliveries_made;
+        self.gifts_dropped += extras_delivered.len() as u32;
+        for owner in extras_delivered {
+            if let Some(player) = self.players.get_mut(owner) {
+                player.gifts_dropped += 1;
+            }
+        }
+        for &(owner, points) in &earned {
+            if let Some(player) = self.players.get_mut(owner) {
+                player.score += points;
+                player.deliveries += 1;
+            }
+        }
+
+        // Break combo if missed, or if a gift was wasted on a chimney
+        if combo_broken {
+            self.break_combo(&broken_by);
+        }
+        self.naughty_meter = (self.naughty_meter + wrong_house * WRONG_HOUSE_PENALTY).min(100);
+        for event in spoiled {
+            if matches!(event, Event::GiftBounced { .. }) {
+                self.emit(Event::Sfx(Sfx::Drop));
+            }
+            self.emit(event);
+        }
+        if landed {
+            self.emit(Event::Sfx(Sfx::Miss));
+        }
+
+        // Effects on delivery
+        if deliveries_made > 0 {
+            self.naughty_meter = self.naughty_meter.saturating_sub(10);
+
+            // One chime for the tick, for the best grade in it
+            let mut best = HitGrade::Good;
+            for event in delivered {
+                if let Event::Delivered { grade, .. } = event {
+                    self.grade_counts[grade as usize] += 1;
+                    best = best.min(grade);
+                }
+                self.emit(event);
+            }
+            self.emit(Event::Sfx(match best {
+                HitGrade::Perfect => Sfx::Perfect,
+                HitGrade::Great => Sfx::Great,
+                HitGrade::Good => Sfx::Delivery,
+            }));
+
+            // Add combo for each delivery
+            for (owner, _) in earned {
+                self.add_combo(owner);
+            }
+
+            self.check_level_up();
+        }
+
+        for (i, owner) in enemy_hits {
+            self.hit_enemy(i, owner);
+        }
+
+        // Clean up finished gifts
+        self.gifts.retain(|g| g.outcome.in_flight());
+    }
+
+
+    // ENEMIES
+
+
+    /// Spawn from the level's roster and move everything but Krampus
+    fn update_enemies(&mut self) {
+        if self.mode == GameMode::Delivering {
+            self.enemy_spawn_timer = self.enemy_spawn_timer.saturating_sub(1);
+            if self.enemy_spawn_timer == 0 {
+                self.spawn_enemy();
+            }
+        }
+
+        let time_scale = self.time_scale();
+        let scroll_speed = self.scroll_speed * time_scale;
+        let mut throws = vec![];
+        for enemy in &mut self.enemies {
+            if !enemy.active || enemy.kind == EnemyKind::Krampus { continue; }
+            enemy.age += 1;
+            match enemy.kind {
+                EnemyKind::Krampus => {}  // Moved by update_krampus
+                EnemyKind::Minion => {
+                    // Long sine swoop across the lane, faster than the scenery
+                    enemy.x -= scroll_speed + 2.0 * time_scale;
+                    enemy.y = enemy.base_y + (enemy.age as f32 * 0.05).sin() * 40.0;
+                }
+                EnemyKind::CoalThrower => {
+                    enemy.x -= scroll_speed;
+                    enemy.timer = enemy.timer.saturating_sub(1);
+                    if enemy.timer == 0 && enemy.x > PLAYER_XS[0] + 40.0 && enemy.x < SCREEN_W - 10.0 {
+                        enemy.timer = COAL_THROW_INTERVAL;
+                        throws.push((enemy.x, enemy.y - 8.0));
+                    }
+                }
+                EnemyKind::SnowGolem => {
+                    enemy.x -= scroll_speed;
+                }
+            }
+            if enemy.x < -40.0 {
+                enemy.active = false;
+            }
+        }
+
+        let speed = self.config().projectile_speed * 0.7;
+        for (x, y) in throws {
+            let target = &self.players[self.nearest_player(x, y)];
+            let angle = (target.y - y).atan2(target.x - x);
+            self.spawn_projectile(EnemyKind::CoalThrower, x, y, angle, speed, Motion::default());
+        }
+
+        // Minions ram the sleighs; under star power they come off worse
+        let mut rammed: Vec<(usize, usize)> = vec![];
+        for (i, e) in self.enemies.iter().enumerate() {
+            if !e.active || e.kind != EnemyKind::Minion { continue; }
+            let reach = (e.kind.radius() + 10.0).powi(2);
+            if let Some(p) = self.players.iter().position(|p| p.flying() && (e.x - p.x).powi(2) + (e.y - p.y).powi(2) < reach) {
+                rammed.push((i, p));
+            }
+        }
+        for (i, p) in rammed {
+            if self.has_powerup(PowerUpKind::Star) {
+                self.hit_enemy(i, p);
+            } else {
+                self.enemies[i].active = false;
+                if !self.invulnerable(p) {
+                    self.hurt_player(p);
+                }
+            }
+        }
+
+        self.enemies.retain(|e| e.active);
+    }
+
+    fn spawn_enemy(&mut self) {
+        let (min, max) = self.config().enemy_interval;
+        self.enemy_spawn_timer = min + self.rng.next_u32() % (max - min + 1);
+
+        let count = self.config().enemies.len();
+        if count == 0 { return; }
+        let pick = self.rng.next_u32() as usize % count;
+        let kind = self.config().enemies[pick];
+
+        match kind {
+            EnemyKind::Krampus => {}  // Has his own attack timer
+            EnemyKind::Minion => {
+                let y = self.rng.range(40.0, SCREEN_H * 0.55);
+                self.enemies.push(Enemy::new(kind, SCREEN_W + 20.0, y));
+            }
+            EnemyKind::CoalThrower | EnemyKind::SnowGolem => {
+                // Claim a free house that is still coming on screen
+                let Some(i) = self.chimneys.iter().position(|c| {
+                    c.x > SCREEN_W * 0.8
+                        && c.open()
+                        && !self.enemies.iter().any(|e| e.kind.on_rooftop() && (e.x - c.x).abs() < 40.0)
+                }) else {
+                    return;
+                };
+                let (cx, cy) = (self.chimneys[i].x, self.chimneys[i].y);
+                let enemy = if kind == EnemyKind::SnowGolem {
+                    self.chimneys[i].blocked = true;
+                    Enemy::new(kind, cx + GOLEM_OFFSET_X, cy - 24.0)
+                } else {
+                    Enemy { timer: COAL_THROW_INTERVAL / 2, ..Enemy::new(kind, cx - 12.0, cy - 2.0) }
+                };
+                self.enemies.push(enemy);
+            }
+        }
+    }
+
+    /// Land a hit on a non-boss enemy, crediting the player who landed it
+    fn hit_enemy(&mut self, i: usize, player: usize) {
+        let enemy = &mut self.enemies[i];
+        if !enemy.active { return; }
+        enemy.health = enemy.health.saturating_sub(1);
+        let (x, y, kind) = (enemy.x, enemy.y, enemy.kind);
+
+        if enemy.health > 0 {
+            self.emit(Event::EnemyHurt { x, y });
+            self.emit(Event::Sfx(Sfx::Hit));
+            return;
+        }
+
+        enemy.active = false;
+        if kind == EnemyKind::SnowGolem
+            && let Some(chimney) = self.chimneys.iter_mut().find(|c| c.blocked && (c.x + GOLEM_OFFSET_X - x).abs() < 4.0)
+        {
+            chimney.blocked = false;
+        }
+        let points = self.boosted(kind.points());
+        self.score += points;
+        if let Some(player) = self.players.get_mut(player) {
+            player.score += points;
+        }
+        self.emit(Event::EnemyDefeated { x, y, kind, points });
+        self.emit(Event::Sfx(Sfx::Delivery));
+    }
+
+
+    // KRAMPUS SYSTEM
+
+
+    fn check_krampus_trigger(&mut self) {
+        if self.mode != GameMode::Delivering { return; }
+
+        // Trigger conditions: timer (or a campaign night's schedule) or naughty meter
+        let due = if self.night().is_some() {
+            let due = self.krampus_due.first().is_some_and(|&at| self.distance >= at);
+            if due {
+                self.krampus_due.remove(0);
+            }
+            due
+        } else {
+            self.krampus_attack_timer = self.krampus_attack_timer.saturating_sub(1);
+            self.krampus_attack_timer == 0
+        };
+
+        if due || self.naughty_meter >= 80 {
+            // Start warning countdown
+            self.krampus_warning = 120; // 2 seconds warning
+            self.transition(GameMode::KrampusWarning);
+            self.emit(Event::KrampusWarning);
+            self.emit(Event::Sfx(Sfx::Warning));
+        }
+    }
+
+    fn update_krampus_warning(&mut self) {
+        if self.krampus_warning > 0 {
+            self.krampus_warning -= 1;
+
+            if self.krampus_warning == 0 {
+                // Krampus attack begins!
+                let y = self.rng.range(40.0, SCREEN_H - 40.0);
+                let mut krampus = Enemy::new(EnemyKind::Krampus, SCREEN_W + 30.0, y);
+                krampus.health = self.config().krampus_health;
+                self.krampus_duration = self.config().krampus_duration;
+                self.krampus_max_health = krampus.health;
+                self.krampus_stage = 1;
+
+                // Minions clear the sky for him; rooftop enemies wait on their houses
+                self.enemies.retain(|e| e.kind.on_rooftop());
+                self.enemies.push(krampus);
+
+                self.transition(GameMode::Krampus);
+                self.emit(Event::KrampusArrived);
+            }
+        }
+    }
+
+    /// Krampus while he is attacking
+    pub fn krampus(&self) -> Option<&Enemy> {
+        self.enemies.iter().find(|e| e.kind == EnemyKind::Krampus)
+    }
+
+    fn krampus_mut(&mut self) -> Option<&mut Enemy> {
+        self.enemies.iter_mut().find(|e| e.kind == EnemyKind::Krampus)
+    }
+
+    fn update_krampus(&mut self) {
+        let (frame, stage) = (self.frame, self.krampus_stage);
+        let Some((kx, ky)) = self.krampus().map(|k| (k.x, k.y)) else { return };
+        let player_y = self.players[self.nearest_player(kx, ky)].y;
+        let Some(krampus) = self.krampus_mut() else { return };
+        krampus.age += 1;
+
+        // Krampus flies in from right
+        if krampus.x > SCREEN_W - 60.0 {
+            krampus.x -= 3.0; // Faster entry
+        }
+
+        // Krampus tracks player Y more aggressively, and harder once wounded
+        let tracking = match stage {
+            1 => 0.035,
+            2 => 0.045,
+            _ => 0.06,
+        };
+        let dy = player_y - krampus.y;
+        krampus.y += dy * tracking;
+
+        // Add bobbing motion for menace (wilder when enraged)
+        let bob = if stage >= 3 { 1.2 } else { 0.5 };
+        krampus.y += (frame as f32 / 10.0).sin() * bob;
+
+        // Fire projectiles - rate increases with level and stage
+        let fire_rate = match self.krampus_stage {
+            1 => self.config().fire_rate,
+            2 => self.config().fire_rate * 3 / 4,
+            _ => self.config().fire_rate / 2,
+        };
+        // A long pattern finishes before the next one starts
+        if self.frame.is_multiple_of(fire_rate.max(5)) && self.volleys.is_empty() {
+            self.fire_projectile_pattern();
+        }
+        self.update_volleys();
+
+        // Duration countdown
+        self.krampus_duration = self.krampus_duration.saturating_sub(1);
+
+        if self.krampus_duration == 0 {
+            // Survival bonus
+            let bonus = self.survival_bonus();
+            self.krampus_retreat(bonus);
+            self.emit(Event::KrampusSurvived { bonus });
+            self.emit(Event::Sfx(Sfx::Survive));
+        }
+    }
+
+    fn survival_bonus(&self) -> u32 {
+        200 + self.level * 50
+    }
+
+    /// Krampus leaves, either because time ran out or he was beaten
+    fn krampus_retreat(&mut self, bonus: u32) {
+        self.enemies.retain(|e| e.kind != EnemyKind::Krampus);
+        self.krampus_attack_timer = self.config().krampus_interval;
+        self.naughty_meter = 0;
+        self.snowballs.clear();
+        self.volleys.clear();
+        self.score += bonus;
+
+        // Back to normal music
+        self.transition(GameMode::Delivering);
+    }
+
+    // ========================================================================
+    // BOSS FIGHT
+    // ========================================================================
+
+    fn throw_snowball(&mut self, player: usize) {
+        let p = &mut self.players[player];
+        if p.throw_cooldown > 0 { return; }
+        p.throw_cooldown = 18;
+
+        let (x, y) = (p.x + 30.0, p.y);
+        self.snowballs.push(Snowball { x, y, prev_x: x, prev_y: y, active: true, owner: player as u8 });
+        self.emit(Event::SnowballThrown);
+        self.emit(Event::Sfx(Sfx::Drop));
+    }
+
+    fn update_snowballs(&mut self) {
+        for player in &mut self.players {
+            player.throw_cooldown = player.throw_cooldown.saturating_sub(1);
+        }
+
+        let target = self.krampus().map(|k| (k.x, k.y, k.kind.radius()));
+        let mut hits = 0;
+        for ball in &mut self.snowballs {
+            ball.x += 5.0;
+            ball.y += 0.15;  // Slight arc
+
+            if let Some((kx, ky, radius)) = target
+                && (ball.x - kx).powi(2) + (ball.y - ky).powi(2) < radius * radius
+            {
+                ball.active = false;
+                hits += 1;
+            } else if ball.x > SCREEN_W + 10.0 {
+                ball.active = false;
+            }
+        }
+        self.snowballs.retain(|b| b.active);
+
+        for _ in 0..hits {
+            self.hurt_krampus();
+        }
+    }
+
+    fn hurt_krampus(&mut self) {
+        let Some(krampus) = self.krampus_mut() else { return };
+        krampus.health = krampus.health.saturating_sub(1);
+        let (x, y, health) = (krampus.x, krampus.y, krampus.health);
+        self.emit(Event::KrampusHurt { x, y });
+        self.emit(Event::Sfx(Sfx::Delivery));
+
+        if health == 0 {
+            // Beaten early: the survival bonus plus more the sooner he falls
+            let bonus = self.survival_bonus() * 2 + self.krampus_duration * 2;
+            self.projectiles.clear();
+            self.krampus_retreat(bonus);
+            self.emit(Event::KrampusDefeated { x, y, bonus });
+            self.emit(Event::Sfx(Sfx::Survive));
+            return;
+        }
+
+        // Stages at two thirds and one third health
+        let stage = match health * 3 {
+            h if h > self.krampus_max_health * 2 => 1,
+            h if h > self.krampus_max_health => 2,
+            _ => 3,
+        };
+        if stage > self.krampus_stage {
+            self.krampus_stage = stage;
+            self.emit(Event::KrampusStage(stage));
+            self.emit(Event::Sfx(Sfx::Warning));
+        }
+    }
+
+    /// Start the next bullet pattern for this level and boss stage
+    fn fire_projectile_pattern(&mut self) {
+        let patterns = self.config().patterns_for(self.krampus_stage);
+        let pattern = patterns[(self.frame / 60 + self.level) as usize % patterns.len()];
+
+        for emitter in 0..self.levels.patterns.patterns[pattern].emitters.len() {
+            let e = self.levels.patterns.patterns[pattern].emitters[emitter];
+            let jitter = if e.jitter > 0.0 { self.rng.range(-e.jitter, e.jitter) } else { 0.0 };
+            self.volleys.push(Volley { pattern, emitter, timer: e.delay, shot: 0, jitter });
+        }
+    }
+
+    /// Fire any emitter shots that are due
+    fn update_volleys(&mut self) {
+        let mut volleys = std::mem::take(&mut self.volleys);
+        for volley in &mut volleys {
+            if volley.timer > 0 {
+                volley.timer -= 1;
+                continue;
+            }
+            let Some((kx, ky)) = self.krampus().map(|k| (k.x, k.y)) else { break };
+            let e = self.levels.patterns.patterns[volley.pattern].emitters[volley.emitter];
+            let aim = match e.aim {
+                Aim::Player => {
+                    let target = &self.players[self.nearest_player(kx, ky)];
+                    (target.y - ky).atan2(target.x - kx)
+                }
+                Aim::Left => std::f32::consts::PI,
+            };
+            let centre = aim + e.angle + volley.jitter + e.rotate * volley.shot as f32;
+            let speed = self.config().projectile_speed * e.speed;
+            for i in 0..e.count {
+                let offset = (i as f32 - (e.count - 1) as f32 / 2.0) * e.spread;
+                self.spawn_projectile(EnemyKind::Krampus, kx - 15.0, ky, centre + offset, speed, e.motion);
+            }
+            volley.shot += 1;
+            volley.timer = e.interval.saturating_sub(1);
+        }
+        volleys.retain(|v| v.shot < self.levels.patterns.patterns[v.pattern].emitters[v.emitter].repeat);
+        self.volleys = volleys;
+    }
+
+    fn spawn_projectile(&mut self, source: EnemyKind, x: f32, y: f32, angle: f32, speed: f32, motion: Motion) {
+        self.projectiles.push(Projectile {
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vel_x: angle.cos() * speed,
+            vel_y: angle.sin() * speed,
+            speed,
+            angle,
+            age: 0,
+            motion,
+            source,
+            target: None,
+            active: true,
+        });
+    }
+
+    fn update_projectiles(&mut self) {
+        // Where each sleigh is, if it can be hit right now
+        let targets: Vec<(f32, f32, bool)> = (0..self.players.len())
+            .map(|i| (self.players[i].x, self.players[i].y, self.players[i].flying() && !self.invulnerable(i)))
+            .collect();
+        let flying: Vec<(f32, f32)> = self.players.iter().filter(|p| p.flying()).map(|p| (p.x, p.y)).collect();
+        let mut hit = vec![false; targets.len()];
+
+        let base_speed = self.config().projectile_speed;
+        let time_scale = self.time_scale();
+        let mut splits = vec![];
+
+        for proj in &mut self.projectiles {
+            if !proj.active { continue; }
+
+            let m = proj.motion;
+            proj.age += 1;
+
+            // Steer, then move along the heading
+            if m.homing > 0.0
+                && let Some(&(px, py)) = flying
+                    .iter()
+                    .min_by(|a, b| ((a.0 - proj.x).powi(2) + (a.1 - proj.y).powi(2)).total_cmp(&((b.0 - proj.x).powi(2) + (b.1 - proj.y).powi(2))))
+            {
+                let target = (py - proj.y).atan2(px - proj.x);
+                let mut diff = target - proj.angle;
+                while diff > std::f32::consts::PI { diff -= std::f32::consts::TAU; }
+                while diff < -std::f32::consts::PI { diff += std::f32::consts::TAU; }
+                proj.angle += diff.clamp(-m.homing, m.homing);
+            }
+            proj.angle += m.turn;
+            proj.speed = (proj.speed + m.accel).clamp(0.3, 12.0);
+            proj.vel_x = proj.angle.cos() * proj.speed;
+            proj.vel_y = proj.angle.sin() * proj.speed;
+            proj.x += proj.vel_x * time_scale;
+            proj.y += proj.vel_y * time_scale;
+
+            // Sideways sine wobble, applied as the change since last tick
+            if m.wobble > 0.0 {
+                let t = proj.age as f32 * m.wobble_freq;
+                let shift = m.wobble * (t.sin() - (t - m.wobble_freq).sin()) * time_scale;
+                proj.x -= proj.angle.sin() * shift;
+                proj.y += proj.angle.cos() * shift;
+            }
+
+            if let Some(split) = m.split
+                && proj.age == split.after
+            {
+                proj.active = false;
+                splits.push((proj.source, proj.x, proj.y, proj.angle, split));
+                continue;
+            }
+
+            // Check collision with each player (only if not invincible)
+            for (i, &(px, py, open)) in targets.iter().enumerate() {
+                if !open || !proj.active || proj.target.is_some_and(|t| t as usize != i) { continue; }
+                let dx = proj.x - px;
+                let dy = proj.y - py;
+                let dist = (dx * dx + dy * dy).sqrt();
+
+                if dist < 14.0 {
+                    proj.active = false;
+                    hit[i] = true;
+                }
+            }
+
+            // Remove if off screen
+            if proj.x < -20.0 || proj.x > SCREEN_W + 20.0 ||
+               proj.y < -20.0 || proj.y > SCREEN_H + 20.0 {
+                proj.active = false;
+            }
+        }
+
+        for (i, hit) in hit.into_iter().enumerate() {
+            if hit && self.mode.in_run() {
+                self.hurt_player(i);
+            }
+        }
+
+        self.projectiles.retain(|p| p.active);
+
+        for (source, x, y, angle, split) in splits {
+            for i in 0..split.count {
+                let offset = (i as f32 - (split.count - 1) as f32 / 2.0) * split.spread;
+                self.spawn_projectile(source, x, y, angle + offset, base_speed * split.speed, Motion::default());
+            }
+        }
+    }
+
+
+    /// A player takes a hit from an enemy or its shot. The shield covers the
+    /// whole team; with shared lives everyone still flying loses a heart.
+    fn hurt_player(&mut self, player: usize) {
+        if self.use_shield() {
+            let p = &mut self.players[player];
+            p.invincible_timer = 60;
+            let (x, y) = (p.x, p.y);
+            self.emit(Event::ShieldBlocked { x, y });
+            self.emit(Event::Sfx(Sfx::Survive));
+            return;
+        }
+
+        let shared = self.lives_shared();
+        for (i, p) in self.players.iter_mut().enumerate() {
+            if p.flying() && (shared || i == player) {
+                p.health -= 1;
+            }
+        }
+        self.players[player].invincible_timer = 90; // 1.5 seconds of invincibility
+        for p in &mut self.players {
+            if !p.flying() {
+                p.invincible_timer = 0;
+            }
+        }
+
+        self.emit(Event::PlayerHit);
+        self.emit(Event::Sfx(Sfx::Hit));
+
+        // Versus ends as soon as either sleigh is down
+        let down = match self.run_mode {
+            RunMode::Versus => !self.players.iter().all(Player::flying),
+            _ => !self.players.iter().any(Player::flying),
+        };
+        if down {
+            // Game over music and sound
+            self.transition(GameMode::GameOver);
+            self.emit(Event::Sfx(Sfx::GameOver));
+        }
+    }
+
+    /// Recently hit, or under star power
+    pub fn invulnerable(&self, player: usize) -> bool {
+        self.players[player].invincible_timer > 0 || self.has_powerup(PowerUpKind::Star)
+    }
+
+
+    // SCROLLING
+
+
+    fn update_scroll(&mut self) {
+        self.scroll_x += self.scroll_speed * self.time_scale();
+    }
+}
+
+/// Naughty, visited or nice, and what a nice house asks for
+fn roll_house(rng: &mut Rng, config: &LevelConfig) -> (HouseKind, Option<GiftKind>) {
+    let roll = rng.range(0.0, 1.0);
+    let house = if roll < config.naughty_chance {
+        HouseKind::Naughty
+    } else if roll < config.naughty_chance + config.visited_chance {
+        HouseKind::Visited
+    } else {
+        HouseKind::Nice
+    };
+    // Some nice houses ask for a particular kind of gift
+    let request = if house == HouseKind::Nice && rng.range(0.0, 1.0) < config.request_chance {
+        let wanted: Vec<GiftKind> = GiftKind::ALL.into_iter().filter(|k| k.requestable()).collect();
+        Some(wanted[rng.next_u32() as usize % wanted.len()])
+    } else {
+        None
+    };
+    (house, request)
+}
+
+/// One tick of gift flight under gravity and wind, weighted by the kind of gift
+fn gift_step(gift: &mut FallingGift, wind: f32, time_scale: f32) {
+    gift.x += gift.vel_x * time_scale;
+    gift.y += gift.vel_y;
+    gift.vel_x += wind * gift.kind.drift();
+    gift.vel_y += GIFT_GRAVITY * gift.kind.gravity();
+}
+
+/// Whether a gift at (x, y) has come down on a house rather than into its chimney
+fn on_roof(chimney: &Chimney, x: f32, y: f32, hitbox: f32) -> bool {
+    let dx = x - chimney.x;
+    if chimney.open() && dx.abs() < hitbox { return false; }
+    chimney.roof_y(dx).is_some_and(|roof| y >= roof)
+}
+
+/// Whether a gift at this offset from a chimney top drops into it
+fn in_chimney(dx: f32, dy: f32, hitbox: f32) -> bool {
+    dx.abs() < hitbox && dy > -10.0 && dy < 30.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IDLE: &[Input] = &[];
+
+    /// A classic run with nothing arriving on its own: no chimneys, enemies,
+    /// power-ups or Krampus unless a test puts them there
+    fn quiet_run() -> Simulation {
+        let mut sim = Simulation::new(1);
+        sim.step(&[Input { start: true, ..Input::default() }]);
+        assert_eq!(sim.mode, GameMode::Delivering);
+        sim.chimneys.clear();
+        sim.next_chimney_spawn = f32::MAX;
+        sim.enemy_spawn_timer = u32::MAX;
+        sim.powerup_spawn_timer = u32::MAX;
+        sim.krampus_attack_timer = u32::MAX;
+        sim
+    }
+
+    fn add_chimney(sim: &mut Simulation, x: f32, house: HouseKind) {
+        sim.chimneys.push(Chimney {
+            id: sim.next_chimney_id,
+            x,
+            y: SCREEN_H * 0.78 - 20.0,
+            delivered: false,
+            blocked: false,
+            house,
+            request: None,
+            style: 0,
+            lane: None,
+        });
+        sim.next_chimney_id += 1;
+    }
+
+    /// Drop a gift and step until nothing is left falling
+    fn drop_and_wait(sim: &mut Simulation) -> Vec<Event> {
+        let mut events = sim.step(&[Input { drop: true, ..Input::default() }]);
+        while !sim.gifts.is_empty() {
+            events.extend(sim.step(IDLE));
+        }
+        events
+    }
+
+    fn delivered_points(events: &[Event]) -> Option<u32> {
+        events.iter().find_map(|e| match e {
+            Event::Delivered { points, .. } => Some(*points),
+            _ => None,
+        })
+    }
+
+    /// Put a nice house where a gift dropped now is a perfect hit, drop one
+    /// and fly on until it has come down. Returns every event on the way.
+    fn deliver(sim: &mut Simulation) -> Vec<Event> {
+        sim.chimneys.clear();
+        let player_x = sim.players[0].x;
+        let hits: Vec<f32> = (0..300)
+            .map(|dx| player_x + dx as f32 * 0.5)
+            .filter(|&x| {
+                let mut probe = sim.clone();
+                add_chimney(&mut probe, x, HouseKind::Nice);
+                drop_and_wait(&mut probe)
+                    .iter()
+                    .any(|e| matches!(e, Event::Delivered { grade: HitGrade::Perfect, .. }))
+            })
+            .collect();
+        assert!(!hits.is_empty(), "no chimney position makes a perfect hit");
+        add_chimney(sim, hits[hits.len() / 2], HouseKind::Nice);
+        drop_and_wait(sim)
+    }
+
+    #[test]
+    fn deliveries_score_and_build_the_combo() {
+        let mut sim = quiet_run();
+        for (combo, bonus) in [(1, 0), (2, 50), (3, 100)] {
+            let before = sim.score;
+            let events = deliver(&mut sim);
+            let points = delivered_points(&events).expect("gift was not delivered");
+            assert_eq!(points, (100 + sim.level * 10) * HitGrade::Perfect.percent() / 100, "a centred present is a perfect hit");
+            assert_eq!(sim.score - before, points + bonus);
+            assert_eq!(sim.combo_count, combo);
+        }
+        assert_eq!(sim.deliveries, 3);
+        assert_eq!(sim.max_combo, 3);
+        assert_eq!(sim.grade_counts, [3, 0, 0]);
+        assert_eq!(sim.accuracy(), 100);
+    }
+
+    #[test]
+    fn a_gift_in_the_snow_breaks_the_combo() {
+        let mut sim = quiet_run();
+        deliver(&mut sim);
+        deliver(&mut sim);
+        assert_eq!(sim.combo_count, 2);
+
+        sim.chimneys.clear();
+        let events = drop_and_wait(&mut sim);
+        assert!(events.iter().any(|e| matches!(e, Event::GiftLanded { .. })));
+        assert_eq!(sim.combo_count, 0);
+        assert_eq!(sim.max_combo, 2);
+        assert_eq!(sim.accuracy(), 66);
+    }
+
+    #[test]
+    fn multi_gift_extras_only_count_when_they_deliver() {
+        let mut sim = quiet_run();
+        deliver(&mut sim);
+        deliver(&mut sim);
+        sim.active_powerups.push(ActivePowerUp { kind: PowerUpKind::MultiGift, timer: 600, stacks: 1 });
+
+        // One of the fan goes in; the others miss the delivered chimney
+        let events = deliver(&mut sim);
+        assert!(delivered_points(&events).is_some());
+        assert_eq!(sim.combo_count, 3);
+        assert_eq!(sim.gifts_dropped, 3);
+        assert_eq!(sim.accuracy(), 100);
+        assert!(!events.contains(&Event::Sfx(Sfx::Miss)));
+    }
+
+    #[test]
+    fn the_combo_runs_out_without_deliveries() {
+        let mut sim = quiet_run();
+        deliver(&mut sim);
+        assert_eq!(sim.combo_count, 1);
+        // 3 seconds, then the tick it runs out on
+        for _ in 0..=180 {
+            sim.step(IDLE);
+        }
+        assert_eq!(sim.combo_count, 0);
+    }
+
+    #[test]
+    fn passing_nice_houses_fills_the_naughty_meter() {
+        let mut sim = quiet_run();
+        add_chimney(&mut sim, -41.0, HouseKind::Nice);
+        add_chimney(&mut sim, -41.0, HouseKind::Naughty);
+        add_chimney(&mut sim, -41.0, HouseKind::Visited);
+        sim.step(IDLE);
+        assert_eq!(sim.naughty_meter, MISS_PENALTY, "only the nice house is owed a gift");
+
+        add_chimney(&mut sim, -41.0, HouseKind::Nice);
+        sim.chimneys.last_mut().unwrap().delivered = true;
+        sim.step(IDLE);
+        assert_eq!(sim.naughty_meter, MISS_PENALTY, "a delivered house is not a miss");
+    }
+
+    #[test]
+    fn a_full_naughty_meter_sends_krampus() {
+        let mut sim = quiet_run();
+        for _ in 0..3 {
+            add_chimney(&mut sim, -41.0, HouseKind::Nice);
+        }
+        let events = sim.step(IDLE);
+        assert_eq!(sim.naughty_meter, 3 * MISS_PENALTY);
+        assert!(!events.contains(&Event::KrampusWarning));
+
+        add_chimney(&mut sim, -41.0, HouseKind::Nice);
+        let events = sim.step(IDLE);
+        assert_eq!(sim.naughty_meter, 80);
+        assert!(events.contains(&Event::KrampusWarning));
+        assert_eq!(sim.mode, GameMode::KrampusWarning);
+    }
+
+    #[test]
+    fn the_attack_timer_warns_then_brings_krampus() {
+        let mut sim = quiet_run();
+        sim.krampus_attack_timer = 3;
+        for _ in 0..2 {
+            assert!(!sim.step(IDLE).contains(&Event::KrampusWarning));
+        }
+        assert!(sim.step(IDLE).contains(&Event::KrampusWarning));
+        assert_eq!(sim.mode, GameMode::KrampusWarning);
+        assert!(sim.krampus().is_none());
+
+        // The warning counts down from 120, starting on the tick it was raised
+        for _ in 0..118 {
+            assert!(!sim.step(IDLE).contains(&Event::KrampusArrived));
+        }
+        assert!(sim.step(IDLE).contains(&Event::KrampusArrived));
+        assert_eq!(sim.mode, GameMode::Krampus);
+        let krampus = sim.krampus().expect("Krampus is on screen");
+        assert_eq!(krampus.health, sim.config().krampus_health);
+        assert_eq!(sim.krampus_duration, sim.config().krampus_duration);
+    }
+
+    #[test]
+    fn a_split_bullet_bursts_into_its_children() {
+        use crate::patterns::Split;
+
+        let mut sim = quiet_run();
+        let split = Split { after: 20, count: 5, spread: 0.2, speed: 1.5 };
+        let motion = Motion { split: Some(split), ..Motion::default() };
+        // High up on the right, heading left, well clear of the sleigh
+        sim.spawn_projectile(EnemyKind::Krampus, SCREEN_W - 40.0, 20.0, std::f32::consts::PI, 1.0, motion);
+
+        for _ in 0..split.after - 1 {
+            sim.step(IDLE);
+        }
+        assert_eq!(sim.projectiles.len(), 1);
+
+        sim.step(IDLE);
+        assert_eq!(sim.projectiles.len(), split.count as usize);
+        let speed = sim.config().projectile_speed * split.speed;
+        for (i, child) in sim.projectiles.iter().enumerate() {
+            let offset = (i as f32 - 2.0) * split.spread;
+            assert!((child.angle - (std::f32::consts::PI + offset)).abs() < 1e-5);
+            assert_eq!(child.speed, speed);
+            assert!(child.motion.split.is_none(), "children don't split again");
+        }
+    }
+
+    #[test]
+    fn an_endless_seed_always_builds_the_same_street() {
+        let build = |seed: u32| {
+            let mut sim = Simulation::new(seed);
+            sim.run_mode = RunMode::Endless;
+            sim.town_seed = seed;
+            sim.chimneys.clear();
+            for _ in 0..30 {
+                sim.build_chunk();
+            }
+            let rivers: Vec<(f32, f32)> = sim.rivers.iter().map(|r| (r.x, r.width)).collect();
+            let chimneys: Vec<_> = sim.chimneys.iter().map(|c| (c.x, c.y, c.style, c.house, c.request)).collect();
+            (chimneys, rivers, sim.next_chunk_x)
+        };
+        let street = build(77);
+        assert!(!street.0.is_empty());
+        assert_eq!(street, build(77));
+        assert_ne!(street, build(78));
+    }
+
+    #[test]
+    fn daily_power_ups_do_not_depend_on_how_the_run_is_played() {
+        let day = crate::daily::FALLBACK_DAY;
+        // Level, kind and height of the first power-ups and the wait after each,
+        // with the sleighs kept flying
+        let run = |input: &dyn Fn(u32) -> Input| {
+            let mut sim = Simulation::new(day);
+            sim.run_mode = RunMode::Daily(day);
+            sim.step(&[Input { start: true, ..Input::default() }]);
+            let mut spawned = vec![];
+            for tick in 0..20_000 {
+                sim.naughty_meter = 0;
+                for player in &mut sim.players {
+                    player.invincible_timer = 60;
+                }
+                let timer = sim.powerup_spawn_timer;
+                sim.step(&[input(tick)]);
+                if sim.powerup_spawn_timer > timer {
+                    let p = sim.powerups.last().unwrap();
+                    spawned.push((sim.level, p.kind, p.y, sim.powerup_spawn_timer));
+                }
+                if spawned.len() == 4 { break; }
+            }
+            (spawned, sim.rng.seed)
+        };
+        let (idle, idle_rng) = run(&|_| Input::default());
+        let (busy, busy_rng) = run(&|tick| Input { up: tick % 90 < 30, down: tick % 90 >= 60, drop: tick % 25 == 0, ..Input::default() });
+        assert_ne!(idle_rng, busy_rng, "the inputs should change the main stream");
+        assert_eq!(idle.len(), 4);
+        assert_eq!(busy.len(), 4);
+        for (a, b) in idle.iter().zip(&busy) {
+            assert_eq!(a.2, b.2, "{idle:?} {busy:?}");
+            // Each level has its own weights and intervals, so the same rolls
+            // only give the same kind and wait on the same level
+            if a.0 == b.0 {
+                assert_eq!((a.1, a.3), (b.1, b.3), "{idle:?} {busy:?}");
+            }
+        }
+        assert_eq!(idle[0], busy[0]);
+    }
+}
@@ -1,55 +1,246 @@
 // AUDIO SYSTEM MODULE
+//
+// Every track and sound effect the game uses is listed here by id, and all
+// playback goes through `AudioManager`, which crossfades music between game
+// states, ducks the music under important effects and keeps the same effect
+// from stacking within one frame.
 
-#![allow(dead_code)]
 use turbo::audio;
-use crate::types::*;
-
-/// Play background music based on current game mode
-pub fn play_mode_music(mode: u8) {
-    // Stop all music tracks first
-    audio::stop("start");
-    audio::stop("game");
-    audio::stop("krampus");
-    audio::stop("game_over");
-    
-    // Play appropriate track for current mode
-    match mode {
-        MODE_TITLE => audio::play("start"),
-        MODE_DELIVERING => audio::play("game"),
-        MODE_KRAMPUS => audio::play("krampus"),
-        MODE_GAMEOVER => audio::play("game_over"),
-        _ => {}
-    }
-}
-
-/// Keep music looping (call every frame)
-pub fn update_music(mode: u8) {
-    match mode {
-        MODE_TITLE => {
-            if !audio::is_playing("start") {
-                audio::play("start");
-            }
+
+/// Frames a music crossfade takes
+const FADE_TICKS: u32 = 45;
+/// Music level while ducked under an important effect
+const DUCK_LEVEL: f32 = 0.35;
+/// Frames the music stays ducked before easing back
+const DUCK_TICKS: u32 = 40;
+/// How fast ducked music recovers per frame
+const DUCK_RECOVERY: f32 = 0.03;
+
+/// Looping background tracks
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub enum Music {
+    Title,
+    Game,
+    Krampus,
+    GameOver,
+}
+
+impl Music {
+    pub const ALL: [Music; 4] = [Self::Title, Self::Game, Self::Krampus, Self::GameOver];
+
+    /// Asset name in audio/
+    pub fn asset(self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Game => "game",
+            Self::Krampus => "krampus",
+            Self::GameOver => "game_over",
         }
-        MODE_DELIVERING => {
-            if !audio::is_playing("game") {
-                audio::play("game");
-            }
+    }
+}
+
+/// One-shot sound effects (Krampus arriving has none: his track starting is the cue)
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub enum Sfx {
+    Start,
+    Drop,
+    Delivery,
+    Great,
+    Perfect,
+    Miss,
+    Hit,
+    Survive,
+    Warning,
+    GameOver,
+}
+
+impl Sfx {
+    pub const ALL: [Sfx; 10] = [
+        Self::Start, Self::Drop, Self::Delivery, Self::Great, Self::Perfect, Self::Miss,
+        Self::Hit, Self::Survive, Self::Warning, Self::GameOver,
+    ];
+
+    /// Asset name in audio/
+    pub fn asset(self) -> &'static str {
+        match self {
+            Self::Start => "start",
+            Self::Drop => "drop",
+            Self::Delivery => "delivery",
+            Self::Great => "great",
+            Self::Perfect => "perfect",
+            Self::Miss => "miss",
+            Self::Hit => "hit",
+            Self::Survive => "survive",
+            Self::Warning => "warning",
+            Self::GameOver => "game_over_sting",
         }
-        MODE_KRAMPUS => {
-            if !audio::is_playing("krampus") {
-                audio::play("krampus");
+    }
+
+    /// Effects the player must not miss pull the music down while they play
+    pub fn ducks_music(self) -> bool {
+        matches!(self, Self::Warning | Self::Hit)
+    }
+
+    fn bit(self) -> u16 {
+        1 << self as u16
+    }
+}
+
+/// Owns all music and effect playback
+#[turbo::serialize]
+pub struct AudioManager {
+    music_volume: f32,
+    sfx_volume: f32,
+    current: Option<Music>,     // Track fading in or playing
+    outgoing: Option<Music>,    // Track fading out
+    fade: u32,                  // Frames left in the crossfade
+    paused: bool,
+    duck: f32,                  // Music multiplier, DUCK_LEVEL..1.0
+    duck_timer: u32,
+    played_this_frame: u16,     // Sfx bits already started this frame
+}
+
+impl Default for AudioManager {
+    fn default() -> Self {
+        Self {
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            current: None,
+            outgoing: None,
+            fade: 0,
+            paused: false,
+            duck: 1.0,
+            duck_timer: 0,
+            played_this_frame: 0,
+        }
+    }
+}
+
+impl AudioManager {
+    pub fn set_volumes(&mut self, music: f32, sfx: f32) {
+        self.music_volume = music;
+        self.sfx_volume = sfx;
+        self.apply_music_volume();
+    }
+
+    /// Switch to a new background track (or silence), crossfading from the old one
+    pub fn play_music(&mut self, track: Option<Music>) {
+        self.paused = false;
+        if track == self.current {
+            if let Some(track) = track {
+                audio::play(track.asset());
             }
+            self.apply_music_volume();
+            return;
         }
-        MODE_GAMEOVER => {
-            if !audio::is_playing("game_over") {
-                audio::play("game_over");
+
+        // A track still fading out from an earlier switch is cut now
+        if let Some(old) = self.outgoing.take()
+            && Some(old) != track
+        {
+            audio::stop(old.asset());
+        }
+        self.outgoing = self.current;
+        self.current = track;
+        self.fade = FADE_TICKS;
+
+        if let Some(track) = track {
+            audio::set_volume(track.asset(), 0.0);
+            audio::play(track.asset());
+        }
+        self.apply_music_volume();
+    }
+
+    pub fn pause_music(&mut self) {
+        self.paused = true;
+        if let Some(old) = self.outgoing.take() {
+            audio::stop(old.asset());
+        }
+        self.fade = 0;
+        if let Some(track) = self.current {
+            audio::pause(track.asset());
+        }
+    }
+
+    pub fn resume_music(&mut self) {
+        self.paused = false;
+        if let Some(track) = self.current {
+            audio::play(track.asset());
+        }
+        self.apply_music_volume();
+    }
+
+    /// Play a one-shot effect; repeats within the same frame are dropped
+    pub fn play_sfx(&mut self, sfx: Sfx) {
+        if self.played_this_frame & sfx.bit() != 0 { return; }
+        self.played_this_frame |= sfx.bit();
+
+        audio::play(sfx.asset());
+        audio::set_volume(sfx.asset(), self.sfx_volume);
+
+        if sfx.ducks_music() {
+            self.duck = DUCK_LEVEL;
+            self.duck_timer = DUCK_TICKS;
+            self.apply_music_volume();
+        }
+    }
+
+    /// Call once per displayed frame before anything plays
+    pub fn begin_frame(&mut self) {
+        self.played_this_frame = 0;
+    }
+
+    /// Advance fades and ducking and keep the current track looping (call every tick)
+    pub fn update(&mut self) {
+        if self.paused { return; }
+
+        if self.fade > 0 {
+            self.fade -= 1;
+            if self.fade == 0
+                && let Some(old) = self.outgoing.take()
+            {
+                audio::stop(old.asset());
             }
         }
-        _ => {}
+
+        if self.duck_timer > 0 {
+            self.duck_timer -= 1;
+        } else {
+            self.duck = (self.duck + DUCK_RECOVERY).min(1.0);
+        }
+
+        if let Some(track) = self.current
+            && !audio::is_playing(track.asset())
+        {
+            audio::play(track.asset());
+        }
+        self.apply_music_volume();
+    }
+
+    fn apply_music_volume(&self) {
+        let t = 1.0 - self.fade as f32 / FADE_TICKS as f32;
+        let level = self.music_volume * self.duck;
+        if let Some(track) = self.current {
+            audio::set_volume(track.asset(), level * t);
+        }
+        if let Some(old) = self.outgoing {
+            audio::set_volume(old.asset(), level * (1.0 - t));
+        }
     }
 }
 
-/// Play a one-shot sound effect
-pub fn play_sfx(name: &str) {
-    audio::play(name);
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn music_and_effects_never_share_an_asset() {
+        for music in Music::ALL {
+            for sfx in Sfx::ALL {
+                assert_ne!(music.asset(), sfx.asset(), "{music:?} and {sfx:?} would restart each other");
+            }
+        }
+    }
 }
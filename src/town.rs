@@ -0,0 +1,305 @@
+// ENDLESS MODE TOWN
+//
+// The endless street is assembled from chunk templates in town.toml (embedded
+// at build time): rows of houses, churches, apartment blocks and rivers. Bad
+// values are clamped or dropped with a warning, in the same way as levels.toml.
+
+use crate::types::Rng;
+use crate::tuning::clamp_warn;
+use turbo::serde::Deserialize;
+use std::collections::BTreeMap;
+
+pub const TOWN_TOML: &str = include_str!("../town.toml");
+
+/// Pixels between the chimneys of an apartment block
+pub const APARTMENT_SECTION: f32 = 36.0;
+
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BuildingKind {
+    House,      // One of the three classic house styles
+    Church,     // Tall spire beside the chimney
+    Apartment,  // A block with a chimney per section
+}
+
+/// One building in a chunk
+#[turbo::serialize]
+#[derive(Copy, PartialEq)]
+pub struct Building {
+    pub kind: BuildingKind,
+    pub x: f32,          // From the start of the chunk
+    pub jitter: f32,     // Random +/- pixels
+    pub chance: f32,     // How often it is built (0-1)
+    pub chimneys: u32,   // Apartment sections
+}
+
+/// A stretch of street to repeat
+#[turbo::serialize]
+pub struct Chunk {
+    pub name: String,
+    pub width: f32,
+    pub weight: u32,
+    pub min_distance: f32,           // Pixels travelled before it can appear
+    pub river: Option<(f32, f32)>,   // Start and width of open water
+    pub buildings: Vec<Building>,
+}
+
+/// Everything the endless street is built from
+#[turbo::serialize]
+pub struct TownPlan {
+    pub chunks: Vec<Chunk>,
+    pub gap: (f32, f32),        // Min/max pixels between chunks at the start
+    pub min_gap: (f32, f32),    // ...once `ramp_distance` has been travelled
+    pub ramp_distance: f32,
+    pub level_distance: f32,    // Pixels travelled per level
+}
+
+// Raw file shape
+#[derive(Deserialize)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawPlan {
+    gap: (f32, f32),
+    min_gap: (f32, f32),
+    ramp_distance: f32,
+    level_distance: f32,
+    chunk: BTreeMap<String, RawChunk>,
+}
+
+impl Default for RawPlan {
+    fn default() -> Self {
+        Self {
+            gap: (60.0, 140.0),
+            min_gap: (20.0, 60.0),
+            ramp_distance: 30000.0,
+            level_distance: 4000.0,
+            chunk: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawChunk {
+    width: f32,
+    weight: u32,
+    min_distance: f32,
+    river: Option<(f32, f32)>,
+    building: Vec<RawBuilding>,
+}
+
+impl Default for RawChunk {
+    fn default() -> Self {
+        Self { width: 240.0, weight: 1, min_distance: 0.0, river: None, building: vec![] }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "turbo::serde", default, deny_unknown_fields)]
+struct RawBuilding {
+    kind: BuildingKind,
+    x: f32,
+    jitter: f32,
+    chance: f32,
+    chimneys: u32,
+}
+
+impl Default for RawBuilding {
+    fn default() -> Self {
+        Self { kind: BuildingKind::House, x: 0.0, jitter: 0.0, chance: 1.0, chimneys: 2 }
+    }
+}
+
+/// A `[min, max]` pair with max raised to min if they are the wrong way round
+fn range_warn(warnings: &mut Vec<String>, name: &str, (lo, hi): (f32, f32)) -> (f32, f32) {
+    let lo = clamp_warn(warnings, "town.toml", "plan", name, lo, 0.0, 1000.0);
+    let hi = clamp_warn(warnings, "town.toml", "plan", name, hi, lo, 1000.0);
+    (lo, hi)
+}
+
+impl TownPlan {
+    /// The built-in town, or the fallback if it fails to parse
+    pub fn embedded() -> Self {
+        Self::parse(TOWN_TOML).0
+    }
+
+    /// A plain street of houses, used when nothing else loads
+    pub fn fallback() -> Self {
+        let raw = RawPlan::default();
+        let house = |x| Building { kind: BuildingKind::House, x, jitter: 20.0, chance: 1.0, chimneys: 1 };
+        Self {
+            chunks: vec![Chunk {
+                name: "street".to_string(),
+                width: 300.0,
+                weight: 1,
+                min_distance: 0.0,
+                river: None,
+                buildings: vec![house(40.0), house(160.0)],
+            }],
+            gap: raw.gap,
+            min_gap: raw.min_gap,
+            ramp_distance: raw.ramp_distance,
+            level_distance: raw.level_distance,
+        }
+    }
+
+    /// Parse and validate the town, returning any problems found. Never fails.
+    pub fn parse(text: &str) -> (Self, Vec<String>) {
+        let mut warnings = vec![];
+        let raw: RawPlan = match toml::from_str(text) {
+            Ok(raw) => raw,
+            Err(err) => {
+                warnings.push(format!("town.toml: {err}; using a plain street"));
+                return (Self::fallback(), warnings);
+            }
+        };
+
+        let w = &mut warnings;
+        let mut chunks = vec![];
+        for (name, raw) in raw.chunk {
+            let width = clamp_warn(w, "town.toml", &name, "width", raw.width, 60.0, 2000.0);
+            let river = raw.river.map(|(start, len)| {
+                let start = clamp_warn(w, "town.toml", &name, "river start", start, 0.0, width);
+                (start, clamp_warn(w, "town.toml", &name, "river width", len, 0.0, width - start))
+            });
+            let buildings = raw
+                .building
+                .into_iter()
+                .enumerate()
+                .map(|(i, b)| {
+                    let place = format!("{name} building {}", i + 1);
+                    Building {
+                        kind: b.kind,
+                        x: clamp_warn(w, "town.toml", &place, "x", b.x, 0.0, width),
+                        jitter: clamp_warn(w, "town.toml", &place, "jitter", b.jitter, 0.0, 100.0),
+                        chance: clamp_warn(w, "town.toml", &place, "chance", b.chance, 0.0, 1.0),
+                        chimneys: clamp_warn(w, "town.toml", &place, "chimneys", b.chimneys, 1, 8),
+                    }
+                })
+                .collect();
+            let min_distance = clamp_warn(w, "town.toml", &name, "min_distance", raw.min_distance, 0.0, f32::MAX);
+            if raw.weight == 0 {
+                w.push(format!("town.toml: {name} has weight 0, skipping it"));
+                continue;
+            }
+            chunks.push(Chunk {
+                name,
+                width,
+                weight: raw.weight,
+                min_distance,
+                river,
+                buildings,
+            });
+        }
+
+        if !chunks.iter().any(|c| c.min_distance == 0.0) {
+            w.push("town.toml: no chunk is available from the start; using a plain street".to_string());
+            return (Self::fallback(), warnings);
+        }
+        let plan = Self {
+            chunks,
+            gap: range_warn(w, "gap", raw.gap),
+            min_gap: range_warn(w, "min_gap", raw.min_gap),
+            ramp_distance: clamp_warn(w, "town.toml", "plan", "ramp_distance", raw.ramp_distance, 1.0, f32::MAX),
+            level_distance: clamp_warn(w, "town.toml", "plan", "level_distance", raw.level_distance, 500.0, f32::MAX),
+        };
+        (plan, warnings)
+    }
+
+    /// Weighted pick among the chunks unlocked `distance` pixels along the street
+    pub fn pick(&self, rng: &mut Rng, distance: f32) -> &Chunk {
+        let open = || self.chunks.iter().filter(|c| c.min_distance <= distance);
+        let total: u32 = open().map(|c| c.weight).sum();
+        let mut roll = rng.next_u32() % total.max(1);
+        for chunk in open() {
+            if roll < chunk.weight {
+                return chunk;
+            }
+            roll -= chunk.weight;
+        }
+        &self.chunks[0]
+    }
+
+    /// Space before the next chunk, tightening as the run goes on
+    pub fn gap_at(&self, rng: &mut Rng, distance: f32) -> f32 {
+        let t = (distance / self.ramp_distance).min(1.0);
+        let lo = self.gap.0 + (self.min_gap.0 - self.gap.0) * t;
+        let hi = self.gap.1 + (self.min_gap.1 - self.gap.1) * t;
+        rng.range(lo, hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Names and gaps of the first `n` chunks picked along a street from `seed`
+    fn street(plan: &TownPlan, seed: u32, n: usize) -> Vec<(String, f32)> {
+        let mut rng = Rng::new(seed);
+        let mut distance = 0.0;
+        (0..n)
+            .map(|_| {
+                let chunk = plan.pick(&mut rng, distance);
+                let gap = plan.gap_at(&mut rng, distance);
+                distance += chunk.width + gap;
+                (chunk.name.clone(), gap)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_embedded_town_parses_cleanly() {
+        let (plan, warnings) = TownPlan::parse(TOWN_TOML);
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(plan.chunks.len() > 1);
+    }
+
+    #[test]
+    fn a_seed_always_picks_the_same_street() {
+        let plan = TownPlan::embedded();
+        let first = street(&plan, 1234, 40);
+        assert_eq!(first, street(&plan, 1234, 40));
+        assert_ne!(first, street(&plan, 1235, 40));
+        // Chunks behind `min_distance` only turn up once the street gets that far
+        assert!(first.iter().any(|(name, _)| name == "river"));
+        assert!(street(&plan, 1234, 2).iter().all(|(name, _)| name != "river"));
+    }
+
+    #[test]
+    fn bad_values_are_clamped_or_dropped() {
+        let (plan, warnings) = TownPlan::parse(r#"
+            gap = [80.0, 40.0]
+
+            [chunk.main]
+            width = 10.0
+            building = [{ x = 500.0, chance = 2.0, chimneys = 0 }]
+
+            [chunk.unused]
+            weight = 0
+        "#);
+        assert_eq!(plan.chunks.len(), 1);
+        let main = &plan.chunks[0];
+        assert_eq!(main.width, 60.0);
+        assert_eq!((main.buildings[0].x, main.buildings[0].chance, main.buildings[0].chimneys), (60.0, 1.0, 1));
+        assert_eq!(plan.gap, (80.0, 80.0));
+        assert_eq!(warnings, [
+            "town.toml: main: width = 10.0 is out of range, using 60.0",
+            "town.toml: main building 1: x = 500.0 is out of range, using 60.0",
+            "town.toml: main building 1: chance = 2.0 is out of range, using 1.0",
+            "town.toml: main building 1: chimneys = 0 is out of range, using 1",
+            "town.toml: unused has weight 0, skipping it",
+            "town.toml: plan: gap = 40.0 is out of range, using 80.0",
+        ]);
+    }
+
+    #[test]
+    fn an_unusable_file_falls_back_to_a_plain_street() {
+        let late_only = "[chunk.late]\nmin_distance = 500.0";
+        for text in ["gap = [", "[chunk.x]\nsize = 3", "", late_only] {
+            let (plan, warnings) = TownPlan::parse(text);
+            assert_eq!(warnings.len(), 1, "{text:?}: {warnings:?}");
+            assert_eq!(plan.chunks.len(), 1);
+            assert_eq!(plan.chunks[0].name, "street");
+        }
+    }
+}
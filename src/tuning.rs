@@ -0,0 +1,72 @@
+// TUNING FILE CHECKS
+//
+// Helpers shared by the loaders for the embedded .toml files, so a bad value
+// is handled and reported the same way whichever file it is in.
+
+/// Take `value` if it passes `ok`, otherwise keep `fallback` and note why
+/// (an empty `place` is a top-level setting)
+pub fn pick<T: Copy + std::fmt::Debug>(
+    warnings: &mut Vec<String>,
+    file: &str,
+    place: &str,
+    name: &str,
+    value: Option<T>,
+    fallback: T,
+    ok: impl Fn(T) -> bool,
+) -> T {
+    match value {
+        Some(v) if ok(v) => v,
+        Some(v) => {
+            let place = if place.is_empty() { String::new() } else { format!("{place}: ") };
+            warnings.push(format!("{file}: {place}{name} = {v:?} is out of range, using {fallback:?}"));
+            fallback
+        }
+        None => fallback,
+    }
+}
+
+/// Clamp a value into range, noting it (under `file` and `place`) if it had to move
+pub fn clamp_warn<T: PartialOrd + Copy + std::fmt::Debug>(
+    warnings: &mut Vec<String>,
+    file: &str,
+    place: &str,
+    name: &str,
+    value: T,
+    min: T,
+    max: T,
+) -> T {
+    let clamped = if value < min { min } else if value > max { max } else { value };
+    if clamped != value {
+        warnings.push(format!("{file}: {place}: {name} = {value:?} is out of range, using {clamped:?}"));
+    }
+    clamped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_keeps_the_fallback_for_bad_values() {
+        let mut warnings = vec![];
+        assert_eq!(pick(&mut warnings, "levels.toml", "level 2", "wind", Some(0.05), 0.0, |v| v < 0.1), 0.05);
+        assert_eq!(pick(&mut warnings, "levels.toml", "level 2", "wind", None, 0.0, |v| v < 0.1), 0.0);
+        assert!(warnings.is_empty());
+
+        assert_eq!(pick(&mut warnings, "levels.toml", "level 2", "wind", Some(0.5), 0.0, |v| v < 0.1), 0.0);
+        assert_eq!(pick(&mut warnings, "campaign.toml", "", "target", Some(0), 10, |v| v > 0), 10);
+        assert_eq!(warnings, [
+            "levels.toml: level 2: wind = 0.5 is out of range, using 0.0",
+            "campaign.toml: target = 0 is out of range, using 10",
+        ]);
+    }
+
+    #[test]
+    fn clamp_warn_notes_values_it_moves() {
+        let mut warnings = vec![];
+        assert_eq!(clamp_warn(&mut warnings, "town.toml", "plan", "gap", 5.0, 0.0, 10.0), 5.0);
+        assert!(warnings.is_empty());
+        assert_eq!(clamp_warn(&mut warnings, "patterns.toml", "spiral emitter 1", "count", 99, 1, 36), 36);
+        assert_eq!(warnings, ["patterns.toml: spiral emitter 1: count = 99 is out of range, using 36"]);
+    }
+}
@@ -1,57 +1,471 @@
 // CONSTANTS AND DATA TYPES
 
+use crate::patterns::Motion;
+use crate::sound::Music;
+
 // Screen dimensions
 pub const SCREEN_W: f32 = 384.0;
 pub const SCREEN_H: f32 = 216.0;
 pub const PLAYER_X: f32 = 60.0;
+/// Up to two sleighs, each flying at its own column
+pub const MAX_PLAYERS: usize = 2;
+pub const PLAYER_XS: [f32; MAX_PLAYERS] = [PLAYER_X, PLAYER_X + 56.0];
+/// Versus: deliveries that win the race, and the combo steps that send an attack
+pub const VERSUS_TARGET: u32 = 20;
+pub const VERSUS_SEND_EVERY: u32 = 3;
 pub const PLAYER_SPEED: f32 = 3.0;
 
+// Fixed timestep: all speeds and timers are per tick at this rate
+pub const TICK_RATE: u64 = 60;
+pub const MAX_TICKS_PER_FRAME: u32 = 5;  // Drop time beyond this (e.g. after a tab was hidden)
+
 // Base colors
 pub const COLOR_SNOW: u32 = 0xf0f8ffff;
 pub const COLOR_CHIMNEY: u32 = 0x8b4513ff;
 pub const COLOR_GOLD: u32 = 0xffd700ff;
 pub const COLOR_CANDY: u32 = 0xff4444ff;
 pub const COLOR_STAR: u32 = 0xffff00ff;
-
-// Level-based sky colors
-pub const SKY_COLORS: [u32; 5] = [
-    0x1a2744ff,  // Level 1: Deep night
-    0x0f1f3aff,  // Level 2: Darker midnight
-    0x2a1a44ff,  // Level 3: Purple twilight
-    0x0a1020ff,  // Level 4: Near black
-    0x220000ff,  // Level 5: Blood moon red
-];
-
-// Game Modes
-pub const MODE_TITLE: u8 = 0;
-pub const MODE_DELIVERING: u8 = 1;
-pub const MODE_KRAMPUS: u8 = 2;
-pub const MODE_GAMEOVER: u8 = 3;
-pub const MODE_PAUSED: u8 = 4;
-
-// Power-up types
-pub const POWERUP_HEALTH: u8 = 0;      // Candy cane - restore health
-pub const POWERUP_INVINCIBLE: u8 = 1;  // Star - temporary invincibility
+pub const PLAYER_COLORS: [u32; 2] = [0xff6666ff, 0x66ccffff];  // P1 and P2 tags in co-op
 
 // DATA STRUCTURES
 
+/// Top-level game state
+///
+/// Title -> Delivering -> KrampusWarning -> Krampus -> Delivering ...
+/// Any in-run state can pause (and resume to where it was) or end in GameOver,
+/// and GameOver restarts straight into Delivering.
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Title,
+    Delivering,
+    KrampusWarning,
+    Krampus,
+    Paused,
+    GameOver,
+}
+
+impl GameMode {
+    /// Whether a run is in progress (including while paused)
+    pub fn in_run(self) -> bool {
+        matches!(self, Self::Delivering | Self::KrampusWarning | Self::Krampus | Self::Paused)
+    }
+
+    /// Whether the state machine allows moving from `self` to `next`.
+    /// Resuming from Paused is further limited to the state that was paused.
+    pub fn can_transition(self, next: GameMode) -> bool {
+        use GameMode::*;
+        matches!(
+            (self, next),
+            (Title, Delivering)
+                | (Delivering, KrampusWarning)
+                | (KrampusWarning, Krampus)
+                | (Krampus, Delivering)
+                | (Delivering | KrampusWarning | Krampus, Paused)
+                | (Paused, Delivering | KrampusWarning | Krampus)
+                | (Delivering | KrampusWarning | Krampus | Paused, GameOver)
+                | (GameOver, Delivering)
+        )
+    }
+
+    /// Looping music track for this state (the warning keeps the delivery tune)
+    pub fn music(self) -> Option<Music> {
+        match self {
+            Self::Title => Some(Music::Title),
+            Self::Delivering | Self::KrampusWarning => Some(Music::Game),
+            Self::Krampus => Some(Music::Krampus),
+            Self::GameOver => Some(Music::GameOver),
+            Self::Paused => None,
+        }
+    }
+}
+
+/// Which kind of run the title screen starts
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum RunMode {
+    #[default]
+    Classic,       // Random chimneys, level up by deliveries
+    Endless,       // A seeded town built from town.toml, level up by distance
+    Campaign(u8),  // One night of campaign.toml (1 = December 1st)
+    Daily(u32),    // Classic rules on the seed for a day (days since the Unix epoch)
+    Versus,        // Two players racing on their own lanes of chimneys
+}
+
+impl RunMode {
+    pub const ALL: [RunMode; 5] = [Self::Classic, Self::Endless, Self::Campaign(1), Self::Daily(0), Self::Versus];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Classic => "Classic",
+            Self::Endless => "Endless",
+            Self::Campaign(_) => "Campaign",
+            Self::Daily(_) => "Daily",
+            Self::Versus => "Versus",
+        }
+    }
+
+    /// Step through the modes, wrapping around
+    pub fn cycle(self, forward: bool) -> Self {
+        let n = Self::ALL.len();
+        let kind = std::mem::discriminant(&self);
+        let i = Self::ALL.iter().position(|m| std::mem::discriminant(m) == kind).unwrap_or(0);
+        Self::ALL[if forward { (i + 1) % n } else { (i + n - 1) % n }]
+    }
+}
+
+/// Screens layered over the game that never touch the simulation
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum Menu {
+    #[default]
+    None,
+    Leaderboard,
+    Options,
+}
+
+/// Three-letter initials being typed in for a new leaderboard entry
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub struct InitialsEntry {
+    pub rank: usize,       // Leaderboard slot the run landed in
+    pub letters: [u8; 3],
+    pub cursor: usize,
+}
+
+impl InitialsEntry {
+    pub fn new(rank: usize, letters: [u8; 3]) -> Self {
+        Self { rank, letters, cursor: 0 }
+    }
+
+    /// Step the letter under the cursor through A-Z, wrapping around
+    pub fn cycle(&mut self, up: bool) {
+        let c = &mut self.letters[self.cursor];
+        let i = c.wrapping_sub(b'A').min(25);
+        *c = b'A' + if up { (i + 1) % 26 } else { (i + 25) % 26 };
+    }
+}
+
+/// One sleigh and whoever is flying it
+#[turbo::serialize]
+pub struct Player {
+    pub x: f32,                 // Fixed column on screen
+    pub y: f32,
+    pub prev_y: f32,            // Start-of-tick height, for interpolated drawing
+    pub vel_y: f32,
+    pub tilt: f32,
+    pub invincible_timer: u32,
+    pub health: u32,
+    pub score: u32,             // Points from this player's own gifts and hits
+    pub deliveries: u32,
+    pub gift_kind: GiftKind,    // What their next drop will be
+    pub throw_cooldown: u32,
+    pub gifts_dropped: u32,
+    pub combo: u32,             // Their own run of deliveries, for versus attacks
+    pub combo_timer: u32,
+    pub max_combo: u32,
+    pub naughty_due: u32,       // Versus: naughty houses the other player has sent into their lane
+}
+
+impl Player {
+    pub fn new(x: f32) -> Self {
+        Self {
+            x,
+            y: SCREEN_H / 2.0,
+            prev_y: SCREEN_H / 2.0,
+            vel_y: 0.0,
+            tilt: 0.0,
+            invincible_timer: 0,
+            health: 3,
+            score: 0,
+            deliveries: 0,
+            gift_kind: GiftKind::Present,
+            throw_cooldown: 0,
+            gifts_dropped: 0,
+            combo: 0,
+            combo_timer: 0,
+            max_combo: 0,
+            naughty_due: 0,
+        }
+    }
+
+    /// Still in the run (a player out of hearts sits the rest of it out)
+    pub fn flying(&self) -> bool {
+        self.health > 0
+    }
+
+    /// Share of this player's gifts that were delivered, in percent
+    pub fn accuracy(&self) -> u32 {
+        (self.deliveries * 100).checked_div(self.gifts_dropped).unwrap_or(0)
+    }
+}
+
+/// How a campaign night went, for the results screen
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub struct NightResult {
+    pub night: u8,
+    pub stars: u8,       // 0 if the night was failed
+    pub new_best: bool,  // Beat the saved best score for the night
+}
+
+/// Blend between the previous and current tick position (`t` in 0..1)
+pub fn lerp(prev: f32, current: f32, t: f32) -> f32 {
+    prev + (current - prev) * t
+}
+
+/// Small deterministic LCG so runs are reproducible from a seed
+#[turbo::serialize]
+pub struct Rng {
+    pub seed: u32,
+}
+
+impl Rng {
+    pub fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.seed = self.seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (self.seed >> 16) & 0x7FFF
+    }
+
+    pub fn range(&mut self, min: f32, max: f32) -> f32 {
+        let r = (self.next_u32() % 1000) as f32 / 1000.0;
+        min + r * (max - min)
+    }
+}
+
+/// Open water under the street in endless mode
+#[turbo::serialize]
+pub struct River {
+    pub x: f32,
+    pub width: f32,
+}
+
 /// A chimney target where Santa needs to drop gifts
 #[turbo::serialize]
 pub struct Chimney {
+    pub id: u32,  // Stays the same while other chimneys come and go
     pub x: f32,
     pub y: f32,
     pub delivered: bool,
-    pub style: u8,
+    pub blocked: bool,  // A snow golem is sitting on it
+    pub house: HouseKind,
+    pub request: Option<GiftKind>,  // Gift this house asked for, shown above the roof
+    pub style: u8,  // 0-2 houses, 3 church, 4 apartment section
+    pub lane: Option<u8>,  // Versus: the one player whose gifts it takes
+}
+
+impl Chimney {
+    /// Whether a gift dropped in now would count for anything
+    pub fn open(&self) -> bool {
+        !self.delivered && !self.blocked && self.house != HouseKind::Visited
+    }
+
+    /// Whether gifts from this player go down it at all (in versus, the
+    /// other lane's chimneys let them fall past)
+    pub fn takes(&self, player: u8) -> bool {
+        self.lane.is_none_or(|lane| lane == player)
+    }
+
+    /// Height of the roof `dx` pixels from the house's centre, if it reaches that far
+    /// (matches the roofs `draw_chimney` paints for each style)
+    pub fn roof_y(&self, dx: f32) -> Option<f32> {
+        // The church's spire stands left of its chimney
+        if self.style == 3 && (-44.0..-32.0).contains(&dx) {
+            return Some(self.y - 56.0);
+        }
+        let (half_width, peak, eaves) = match self.style {
+            0 => (30.0, -10.0, 10.0),
+            1 => (27.0, -22.0, 5.0),
+            2 => (38.0, 4.0, 4.0),
+            3 => (50.0, -6.0, 6.0),
+            _ => (18.0, 4.0, 4.0),
+        };
+        let dx = dx.abs();
+        (dx < half_width).then(|| self.y + peak + (eaves - peak) * dx / half_width)
+    }
+}
+
+/// How close to the middle of the chimney mouth a gift went in
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HitGrade {
+    Perfect,
+    Great,
+    Good,
+}
+
+impl HitGrade {
+    pub const ALL: [HitGrade; 3] = [Self::Perfect, Self::Great, Self::Good];
+
+    /// Grade for a gift `dx` pixels off centre of a chimney with this hitbox half-width
+    pub fn for_offset(dx: f32, hitbox: f32) -> Self {
+        let off = dx.abs() / hitbox;
+        if off <= 0.25 {
+            Self::Perfect
+        } else if off <= 0.6 {
+            Self::Great
+        } else {
+            Self::Good
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Perfect => "PERFECT",
+            Self::Great => "GREAT",
+            Self::Good => "GOOD",
+        }
+    }
+
+    /// Score multiplier in percent
+    pub fn percent(self) -> u32 {
+        match self {
+            Self::Perfect => 200,
+            Self::Great => 150,
+            Self::Good => 100,
+        }
+    }
+}
+
+/// Grade text floating up from a chimney after a delivery
+#[turbo::serialize]
+pub struct GradePopup {
+    pub grade: HitGrade,
+    pub x: f32,
+    pub y: f32,
+    pub life: u32,
+}
+
+/// Who lives in a house, going by Santa's list
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum HouseKind {
+    #[default]
+    Nice,     // Wants presents; passing it by counts as a miss
+    Naughty,  // Gets coal; a present here fills the naughty meter
+    Visited,  // Already had its presents, lights out
+}
+
+impl HouseKind {
+    /// Whether this is the right house for a gift of the given kind
+    pub fn wants(self, kind: GiftKind) -> bool {
+        match self {
+            Self::Nice => kind != GiftKind::Coal,
+            Self::Naughty => kind == GiftKind::Coal,
+            Self::Visited => false,
+        }
+    }
+}
+
+/// What the sleigh drops; the player cycles through these
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq, Default)]
+pub enum GiftKind {
+    #[default]
+    Present,  // The standard box
+    Light,    // Small and floaty: falls slowly and rides the wind
+    Heavy,    // Drops fast and barely drifts
+    Fragile,  // Worth double, but breaks on anything short of a Great
+    Coal,     // For naughty houses; nobody else wants it
+}
+
+impl GiftKind {
+    pub const ALL: [GiftKind; 5] = [Self::Present, Self::Light, Self::Heavy, Self::Fragile, Self::Coal];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Present => "Present",
+            Self::Light => "Light",
+            Self::Heavy => "Heavy",
+            Self::Fragile => "Fragile",
+            Self::Coal => "Coal",
+        }
+    }
+
+    /// The next kind when cycling, wrapping around
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&k| k == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Multiple of normal gravity
+    pub fn gravity(self) -> f32 {
+        match self {
+            Self::Present | Self::Fragile => 1.0,
+            Self::Light => 0.55,
+            Self::Heavy => 1.8,
+            Self::Coal => 1.4,
+        }
+    }
+
+    /// Multiple of the wind's push
+    pub fn drift(self) -> f32 {
+        match self {
+            Self::Present | Self::Fragile => 1.0,
+            Self::Light => 2.5,
+            Self::Heavy => 0.3,
+            Self::Coal => 0.5,
+        }
+    }
+
+    /// Multiple of the delivery score
+    pub fn value(self) -> u32 {
+        match self {
+            Self::Present | Self::Light | Self::Heavy | Self::Coal => 1,
+            Self::Fragile => 2,
+        }
+    }
+
+    /// Whether a house might ask for it
+    pub fn requestable(self) -> bool {
+        self != Self::Coal
+    }
+}
+
+/// How a gift's flight turned out, or that it is still going
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub enum GiftOutcome {
+    Falling,
+    Bouncing,    // Glanced off a roof and is tumbling to the ground
+    Delivered,
+    Landed,      // Came down in the snow
+    Lost,        // Left the screen
+    Broken,      // A fragile gift clipped the chimney rim
+    WrongHouse,  // Coal for a nice house, or a present for a naughty one
+    HitEnemy,    // Knocked into a rooftop enemy
+}
+
+impl GiftOutcome {
+    /// Still on screen and moving
+    pub fn in_flight(self) -> bool {
+        matches!(self, Self::Falling | Self::Bouncing)
+    }
+
+    /// Missed, or wasted on a chimney
+    pub fn breaks_combo(self) -> bool {
+        matches!(self, Self::Landed | Self::Lost | Self::Broken | Self::WrongHouse)
+    }
 }
 
 /// A gift that's been dropped and is falling
 #[turbo::serialize]
 pub struct FallingGift {
+    pub kind: GiftKind,
     pub x: f32,
     pub y: f32,
+    pub prev_x: f32,  // Position at the start of the tick, for interpolated drawing
+    pub prev_y: f32,
+    pub vel_x: f32,
     pub vel_y: f32,
-    pub target_chimney: usize,
-    pub active: bool,
+    pub target_chimney: Option<u32>,  // Id of the chimney it was dropped towards
+    pub outcome: GiftOutcome,
+    pub owner: u8,                    // Index of the player who dropped it
+    pub fan: u32,                     // Drop it left the sleigh in, shared by a multi-gift fan
+    pub extra: bool,                  // Rest of a multi-gift fan: only counts if it delivers
 }
 
 /// Krampus projectile
@@ -59,11 +473,118 @@ pub struct FallingGift {
 pub struct Projectile {
     pub x: f32,
     pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
     pub vel_x: f32,
     pub vel_y: f32,
+    pub speed: f32,
+    pub angle: f32,      // Heading in radians (0 = right, positive = clockwise)
+    pub age: u32,        // Ticks since fired
+    pub motion: Motion,  // Acceleration, curving, homing, wobble and splitting
+    pub source: EnemyKind,
+    pub target: Option<u8>,  // Versus: a shot sent by the other player only hits this one
     pub active: bool,
 }
 
+/// A bullet pattern Krampus is partway through firing
+#[turbo::serialize]
+pub struct Volley {
+    pub pattern: usize,  // Index into the pattern library
+    pub emitter: usize,
+    pub timer: u32,      // Ticks until the next shot
+    pub shot: u32,       // Shots fired so far
+    pub jitter: f32,     // Random aim offset for this volley
+}
+
+/// Every kind of enemy; levels.toml schedules all but Krampus by name
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EnemyKind {
+    Krampus,      // The boss, on his own attack timer
+    Minion,       // Swoops through the delivery lane
+    CoalThrower,  // Lobs coal from a rooftop
+    SnowGolem,    // Sits on a chimney until knocked off with a gift
+}
+
+impl EnemyKind {
+    /// Hits it takes to defeat
+    pub fn health(self) -> u32 {
+        match self {
+            Self::Krampus | Self::Minion | Self::CoalThrower => 1,
+            Self::SnowGolem => 2,
+        }
+    }
+
+    /// Score for defeating one
+    pub fn points(self) -> u32 {
+        match self {
+            Self::Krampus => 0,  // Paid as the boss bonus instead
+            Self::Minion => 50,
+            Self::CoalThrower => 150,
+            Self::SnowGolem => 100,
+        }
+    }
+
+    /// Collision radius
+    pub fn radius(self) -> f32 {
+        match self {
+            Self::Krampus => 22.0,
+            Self::Minion => 12.0,
+            Self::CoalThrower => 12.0,
+            Self::SnowGolem => 16.0,
+        }
+    }
+
+    /// Lives on a house and scrolls with it
+    pub fn on_rooftop(self) -> bool {
+        matches!(self, Self::CoalThrower | Self::SnowGolem)
+    }
+}
+
+/// An enemy on screen
+#[turbo::serialize]
+pub struct Enemy {
+    pub kind: EnemyKind,
+    pub x: f32,
+    pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub base_y: f32,   // Centre line of a minion's swoop
+    pub age: u32,      // Ticks since it appeared
+    pub timer: u32,    // Ticks until its next attack
+    pub health: u32,
+    pub active: bool,
+}
+
+impl Enemy {
+    pub fn new(kind: EnemyKind, x: f32, y: f32) -> Self {
+        Self {
+            kind,
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            base_y: y,
+            age: 0,
+            timer: 0,
+            health: kind.health(),
+            active: true,
+        }
+    }
+}
+
+/// Snowball thrown at Krampus during a boss fight
+#[turbo::serialize]
+pub struct Snowball {
+    pub x: f32,
+    pub y: f32,
+    pub prev_x: f32,
+    pub prev_y: f32,
+    pub active: bool,
+    pub owner: u8,  // Index of the player who threw it
+}
+
 /// Snowflake for atmosphere
 #[turbo::serialize]
 pub struct Snowflake {
@@ -85,12 +606,126 @@ pub struct Particle {
     pub size: u32,
 }
 
+/// Kinds of power-up. Picking up one that is already running adds a stack
+/// (up to `max_stacks`) and restarts its timer.
+#[turbo::serialize]
+#[derive(Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    Health,       // Candy cane - restore one heart
+    Star,         // Temporary invincibility
+    MultiGift,    // Each drop releases a spread of gifts (3, then 5)
+    Magnet,       // Falling gifts are pulled towards chimneys
+    SlowMo,       // The world moves at half speed
+    Multiplier,   // Score x2, x3, x4
+    Shield,       // Absorbs one hit per stack
+    ComboExtend,  // Longer window to keep a combo going
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 8] = [
+        Self::Health, Self::Star, Self::MultiGift, Self::Magnet,
+        Self::SlowMo, Self::Multiplier, Self::Shield, Self::ComboExtend,
+    ];
+
+    /// Name used in levels.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Health => "health",
+            Self::Star => "star",
+            Self::MultiGift => "multi_gift",
+            Self::Magnet => "magnet",
+            Self::SlowMo => "slow_mo",
+            Self::Multiplier => "multiplier",
+            Self::Shield => "shield",
+            Self::ComboExtend => "combo_extend",
+        }
+    }
+
+    /// Ticks the effect lasts (0 = instant, or until used up for the shield)
+    pub fn duration(self) -> u32 {
+        match self {
+            Self::Health | Self::Shield => 0,
+            Self::Star | Self::SlowMo => 300,
+            Self::Magnet => 480,
+            Self::MultiGift | Self::Multiplier => 600,
+            Self::ComboExtend => 900,
+        }
+    }
+
+    pub fn max_stacks(self) -> u32 {
+        match self {
+            Self::MultiGift => 2,
+            Self::Multiplier | Self::Shield => 3,
+            _ => 1,
+        }
+    }
+
+    /// Applied once on pickup rather than kept as a running effect
+    pub fn is_instant(self) -> bool {
+        self == Self::Health
+    }
+}
+
+/// A power-up effect currently running
+#[turbo::serialize]
+pub struct ActivePowerUp {
+    pub kind: PowerUpKind,
+    pub timer: u32,   // Ticks left (unused by the shield)
+    pub stacks: u32,
+}
+
 /// Power-up item
 #[turbo::serialize]
 pub struct PowerUp {
     pub x: f32,
     pub y: f32,
-    pub kind: u8,      // POWERUP_HEALTH or POWERUP_INVINCIBLE
+    pub kind: PowerUpKind,
     pub active: bool,
     pub bob_offset: f32,  // For floating animation
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn game_modes_only_move_along_allowed_edges() {
+        use GameMode::*;
+        const MODES: [GameMode; 6] = [Title, Delivering, KrampusWarning, Krampus, Paused, GameOver];
+        const ALLOWED: [(GameMode, GameMode); 15] = [
+            (Title, Delivering),
+            (Delivering, KrampusWarning),
+            (KrampusWarning, Krampus),
+            (Krampus, Delivering),
+            (Delivering, Paused),
+            (KrampusWarning, Paused),
+            (Krampus, Paused),
+            (Paused, Delivering),
+            (Paused, KrampusWarning),
+            (Paused, Krampus),
+            (Delivering, GameOver),
+            (KrampusWarning, GameOver),
+            (Krampus, GameOver),
+            (Paused, GameOver),
+            (GameOver, Delivering),
+        ];
+        for from in MODES {
+            for to in MODES {
+                assert_eq!(from.can_transition(to), ALLOWED.contains(&(from, to)), "{from:?} -> {to:?}");
+            }
+        }
+
+        for (from, to) in [
+            (Title, Krampus),
+            (Title, GameOver),
+            (Delivering, Krampus),
+            (KrampusWarning, Delivering),
+            (GameOver, Paused),
+            (GameOver, Title),
+            (Paused, Title),
+            (Krampus, Krampus),
+        ] {
+            assert!(!from.can_transition(to), "{from:?} -> {to:?}");
+        }
+    }
+}